@@ -0,0 +1,28 @@
+// Assembles `HID_DESCRIPTOR` from descriptors of the report structs, see `build/assemble.rs`.
+// Layout of each report, as serialized by the firmware, is checked against the descriptor
+// by the tests of the host (`host-tests/tests/descriptor.rs`).
+
+use std::fmt::Write;
+use std::{env, fs, path::Path};
+
+#[path = "src/reports.rs"]
+#[allow(dead_code)]
+mod reports;
+
+#[path = "build/assemble.rs"]
+mod assemble;
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=build/assemble.rs");
+    println!("cargo:rerun-if-changed=src/reports.rs");
+
+    let mut source = String::from("pub static HID_DESCRIPTOR: &[u8] = &[");
+    for byte in assemble::descriptors().concat() {
+        write!(source, "{:#04x}, ", byte).unwrap();
+    }
+    source.push_str("];\n");
+
+    let out_dir = env::var("OUT_DIR").unwrap();
+    fs::write(Path::new(&out_dir).join("hid_descriptor.rs"), source).unwrap();
+}
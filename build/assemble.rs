@@ -0,0 +1,115 @@
+// Assembles `HID_DESCRIPTOR` from descriptors of the report structs, tagged with their
+// report IDs and completed with extents of Resolution Multipliers and the hat switch.
+// Shared by the build script and the tests of the host, which check the reports against it.
+
+use usbd_hid::descriptor::SerializedDescriptor;
+
+use crate::reports::*;
+
+pub const MAIN_ITEM: u8 = 0;
+const COLLECTION: u8 = 0xa;
+pub const GLOBAL_ITEM: u8 = 1;
+const LOCAL_ITEM: u8 = 2;
+pub const INPUT: u8 = 0x8;
+pub const FEATURE: u8 = 0xb;
+const LOGICAL_MAXIMUM: u8 = 0x2;
+const PHYSICAL_MINIMUM: u8 = 0x3;
+const PHYSICAL_MAXIMUM: u8 = 0x4;
+pub const REPORT_ID: u8 = 0x8;
+const USAGE: u8 = 0x0;
+const RESOLUTION_MULTIPLIER_USAGE: usize = 0x48;
+const HAT_SWITCH_USAGE: usize = 0x39;
+
+// Short items of the descriptor: prefix, value and position of the next item.
+pub fn items(descriptor: &[u8]) -> impl Iterator<Item = (u8, usize, usize)> + '_ {
+    let mut position = 0;
+    std::iter::from_fn(move || {
+        let prefix = *descriptor.get(position)?;
+        assert_ne!(prefix, 0xfe, "long items are not supported");
+        let data_len = [0, 1, 2, 4][usize::from(prefix & 0b11)];
+        let data = &descriptor[position + 1..position + 1 + data_len];
+        let mut value = [0; 4];
+        value[..data_len].copy_from_slice(data);
+        position += 1 + data_len;
+        Some((prefix, u32::from_le_bytes(value) as usize, position))
+    })
+}
+
+pub fn item_type(prefix: u8) -> (u8, u8) {
+    ((prefix >> 2) & 0b11, prefix >> 4)
+}
+
+// Inserts Report ID item right after the first collection, i.e. the application collection.
+fn with_report_id(descriptor: &[u8], id: u8) -> Vec<u8> {
+    let (_, _, end) = items(descriptor)
+        .find(|(prefix, _, _)| item_type(*prefix) == (MAIN_ITEM, COLLECTION))
+        .expect("descriptor without a collection");
+    let report_id = (REPORT_ID << 4) | (GLOBAL_ITEM << 2) | 1;
+    [&descriptor[..end], &[report_id, id], &descriptor[end..]].concat()
+}
+
+fn short_item(kind: u8, tag: u8, value: u8) -> [u8; 2] {
+    [(tag << 4) | (kind << 2) | 1, value]
+}
+
+// Logical maximum and physical extents of the controls, which the macro can't express:
+// - Resolution Multiplier maps its value (0 or 1) to 1..RESOLUTION_MULTIPLIER,
+// - hat switch points in one of 8 directions, values beyond them are its null state.
+fn extents(usage: usize) -> Option<(u8, u8, u8)> {
+    match usage {
+        RESOLUTION_MULTIPLIER_USAGE => Some((1, 1, RESOLUTION_MULTIPLIER)),
+        HAT_SWITCH_USAGE => Some((7, 0, 0)),
+        _ => None,
+    }
+}
+
+// Sets extents of the controls listed by `extents`. Global items are restored right after
+// each control, so that following items keep what the macro generated.
+fn with_extents(descriptor: &[u8]) -> Vec<u8> {
+    let mut patched = Vec::new();
+    let mut usage = None;
+    let mut logical_maximum: &[u8] = &[];
+    let mut start = 0;
+    for (prefix, value, end) in items(descriptor) {
+        let item = &descriptor[start..end];
+        start = end;
+        match item_type(prefix) {
+            (LOCAL_ITEM, USAGE) => usage = Some(value),
+            (GLOBAL_ITEM, LOGICAL_MAXIMUM) => logical_maximum = item,
+            (MAIN_ITEM, kind) => {
+                let control = matches!(kind, INPUT | FEATURE);
+                if let Some((logical_max, physical_min, physical_max)) =
+                    usage.take().and_then(extents).filter(|_| control)
+                {
+                    patched.extend(short_item(GLOBAL_ITEM, LOGICAL_MAXIMUM, logical_max));
+                    patched.extend(short_item(GLOBAL_ITEM, PHYSICAL_MINIMUM, physical_min));
+                    patched.extend(short_item(GLOBAL_ITEM, PHYSICAL_MAXIMUM, physical_max));
+                    patched.extend(item);
+                    patched.extend(short_item(GLOBAL_ITEM, PHYSICAL_MINIMUM, 0));
+                    patched.extend(short_item(GLOBAL_ITEM, PHYSICAL_MAXIMUM, 0));
+                    patched.extend(logical_maximum);
+                    continue;
+                }
+            }
+            _ => {}
+        }
+        patched.extend(item);
+    }
+    patched
+}
+
+pub fn descriptor<R: ReportId + SerializedDescriptor>() -> Vec<u8> {
+    with_extents(&with_report_id(R::desc(), R::ID))
+}
+
+// Descriptors of all the reports, in the order of `HID_DESCRIPTOR`.
+pub fn descriptors() -> [Vec<u8>; 6] {
+    [
+        descriptor::<MouseReportEx>(),
+        descriptor::<KeyboardReportEx>(),
+        descriptor::<MediaKeyboardReportEx>(),
+        descriptor::<ConfigReportEx>(),
+        descriptor::<AbsolutePointerReportEx>(),
+        descriptor::<GamepadReportEx>(),
+    ]
+}
@@ -0,0 +1,56 @@
+use std::error::Error;
+use std::io::{self, BufRead, BufReader, Read, Write};
+
+use mickey_protocol::{Command, Reply};
+
+pub type Result<T> = std::result::Result<T, Box<dyn Error>>;
+
+// Console of the device, reachable through any byte stream.
+pub struct Device<T: Read + Write> {
+    port: BufReader<T>,
+    // part of the line received before a timeout
+    pending: String,
+}
+
+impl<T: Read + Write> Device<T> {
+    pub fn new(port: T) -> Self {
+        Device {
+            port: BufReader::new(port),
+            pending: String::new(),
+        }
+    }
+
+    // Sends the command, returns lines of its output.
+    pub fn execute(&mut self, command: &Command) -> Result<Vec<String>> {
+        let text = command.to_string();
+        // leading CR terminates anything typed into the console before
+        let port = self.port.get_mut();
+        write!(port, "\r{}\r", text)?;
+        port.flush()?;
+
+        let mut output = Vec::new();
+        loop {
+            let line = self.read_line()?;
+            match Reply::parse(&line) {
+                Reply::Ok => return Ok(output),
+                Reply::Error(reason) => return Err(format!("{}: {}", text, reason).into()),
+                // the console echoes the command
+                Reply::Output(line) if line == text || line.is_empty() => {}
+                Reply::Output(line) => output.push(line.to_string()),
+            }
+        }
+    }
+
+    // Reads a line of output or log, without the terminator.
+    pub fn read_line(&mut self) -> io::Result<String> {
+        loop {
+            if self.port.read_line(&mut self.pending)? == 0 {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+            if self.pending.ends_with('\n') {
+                let line = std::mem::take(&mut self.pending);
+                return Ok(line.trim_end().to_string());
+            }
+        }
+    }
+}
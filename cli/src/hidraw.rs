@@ -0,0 +1,105 @@
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::os::fd::AsRawFd;
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use mickey_protocol::{decode_config_report, encode_config_report};
+use mickey_protocol::{CONFIG_REPORT_ID, CONFIG_REPORT_SIZE};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+// HIDIOCSFEATURE and HIDIOCGFEATURE from linux/hidraw.h
+const fn hidraw_ioctl(nr: u8, len: usize) -> u64 {
+    3 << 30 | (len as u64) << 16 | (b'H' as u64) << 8 | nr as u64
+}
+const HIDIOCSFEATURE: u64 = hidraw_ioctl(0x06, CONFIG_REPORT_SIZE);
+const HIDIOCGFEATURE: u64 = hidraw_ioctl(0x07, CONFIG_REPORT_SIZE);
+
+// Configuration report of the device, presented as a byte stream like the serial port.
+// Written lines are sent as commands, output is read by polling the report.
+pub struct Hidraw {
+    file: File,
+    timeout: Duration,
+    // part of the command written so far
+    command: Vec<u8>,
+    // output received, but not read yet
+    output: Vec<u8>,
+}
+
+impl Hidraw {
+    pub fn open(path: &Path, timeout: Duration) -> io::Result<Self> {
+        let file = OpenOptions::new().read(true).write(true).open(path)?;
+        Ok(Hidraw {
+            file,
+            timeout,
+            command: Vec::new(),
+            output: Vec::new(),
+        })
+    }
+
+    fn ioctl(&self, request: u64, report: &mut [u8; CONFIG_REPORT_SIZE]) -> io::Result<()> {
+        let result =
+            unsafe { libc::ioctl(self.file.as_raw_fd(), request as _, report.as_mut_ptr()) };
+        if result < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+}
+
+impl Read for Hidraw {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let started = Instant::now();
+        while self.output.is_empty() {
+            let mut report = [0; CONFIG_REPORT_SIZE];
+            report[0] = CONFIG_REPORT_ID;
+            self.ioctl(HIDIOCGFEATURE, &mut report)?;
+            let payload = decode_config_report(&report)
+                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "malformed report"))?;
+
+            self.output.extend_from_slice(payload);
+
+            if self.output.is_empty() {
+                if started.elapsed() >= self.timeout {
+                    return Err(io::ErrorKind::TimedOut.into());
+                }
+                thread::sleep(POLL_INTERVAL);
+            }
+        }
+
+        let len = self.output.len().min(buf.len());
+        buf[..len].copy_from_slice(&self.output[..len]);
+        self.output.drain(..len);
+        Ok(len)
+    }
+}
+
+impl Write for Hidraw {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        for &byte in buf {
+            if byte != b'\r' && byte != b'\n' {
+                self.command.push(byte);
+                continue;
+            }
+            let command = std::mem::take(&mut self.command);
+            if command.is_empty() {
+                continue;
+            }
+            let mut report = encode_config_report(&command);
+            if usize::from(report[1]) < command.len() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "command too long",
+                ));
+            }
+            self.ioctl(HIDIOCSFEATURE, &mut report)?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
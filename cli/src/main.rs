@@ -0,0 +1,225 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use clap::{Parser, Subcommand};
+use mickey_protocol::{
+    Command, KeymapEntry, Param, ParamValue, LEARNING_ABORTED, LEARNING_FINISHED,
+};
+
+mod device;
+#[cfg(target_os = "linux")]
+mod hidraw;
+mod settings_file;
+
+use device::{Device, Result};
+use settings_file::{Code, SettingsFile};
+
+/// Configures MicKeyMouse through its serial console.
+#[derive(Parser)]
+struct Cli {
+    /// Serial port of the device
+    #[arg(short, long, default_value = "/dev/ttyACM0")]
+    port: String,
+    /// Configure through the HID feature report of a hidraw device instead, e.g. /dev/hidraw0
+    #[cfg(target_os = "linux")]
+    #[arg(long)]
+    hidraw: Option<PathBuf>,
+    #[command(subcommand)]
+    action: Action,
+}
+
+#[derive(Subcommand)]
+enum Action {
+    /// Print state and parameters of the device
+    Status,
+    /// Write learned codes to a TOML file
+    DumpKeymap { file: PathBuf },
+    /// Bind codes from a TOML file to the buttons and save them
+    LoadKeymap {
+        file: PathBuf,
+        /// Forget codes which are not in the file
+        #[arg(long)]
+        replace: bool,
+    },
+    /// Bind codes of a remote control to the buttons one by one
+    Learn,
+    /// Write all the settings to a TOML file
+    Backup { file: PathBuf },
+    /// Apply settings from a TOML file and save them
+    Restore { file: PathBuf },
+    /// Send a command of the console, e.g. `send mode keyboard`
+    Send {
+        #[arg(required = true)]
+        words: Vec<String>,
+    },
+}
+
+const TIMEOUT: Duration = Duration::from_secs(3);
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+
+    #[cfg(target_os = "linux")]
+    if let Some(path) = &cli.hidraw {
+        // log of the device is printed only on the serial port
+        if matches!(cli.action, Action::Learn) {
+            return Err(
+                "learning can't be followed through hidraw, use `send learn` and `status`".into(),
+            );
+        }
+        let port = hidraw::Hidraw::open(path, TIMEOUT)?;
+        return run(&mut Device::new(port), cli.action);
+    }
+
+    let mut port = serialport::new(&cli.port, 115_200)
+        .timeout(TIMEOUT)
+        .open()?;
+    // console prints the log only while DTR is set,
+    // pseudo terminals have no modem lines, commands work without it
+    if let Err(error) = port.write_data_terminal_ready(true) {
+        eprintln!(
+            "DTR can't be set, log of the device won't be followed: {}",
+            error
+        );
+    }
+
+    run(&mut Device::new(port), cli.action)
+}
+
+fn run<T: Read + Write>(device: &mut Device<T>, action: Action) -> Result<()> {
+    match action {
+        Action::Status => {
+            for line in device.execute(&Command::Status)? {
+                println!("{}", line);
+            }
+            for line in device.execute(&Command::Get(None))? {
+                println!("{}", line);
+            }
+        }
+        Action::DumpKeymap { file } => {
+            let settings = SettingsFile {
+                keymap: read_keymap(device)?,
+                ..Default::default()
+            };
+            fs::write(file, toml::to_string(&settings)?)?;
+        }
+        Action::LoadKeymap { file, replace } => {
+            let settings: SettingsFile = toml::from_str(&fs::read_to_string(file)?)?;
+            if replace {
+                device.execute(&Command::KeymapClear)?;
+            }
+            write_keymap(device, &settings)?;
+            device.execute(&Command::Save)?;
+        }
+        Action::Learn => learn(device)?,
+        Action::Backup { file } => {
+            let settings = SettingsFile {
+                enabled: Some(read_enabled(device)?),
+                params: read_params(device)?,
+                keymap: read_keymap(device)?,
+            };
+            fs::write(file, toml::to_string(&settings)?)?;
+        }
+        Action::Restore { file } => {
+            let settings: SettingsFile = toml::from_str(&fs::read_to_string(file)?)?;
+            match settings.enabled {
+                Some(true) => device.execute(&Command::Enable)?,
+                Some(false) => device.execute(&Command::Disable)?,
+                None => Vec::new(),
+            };
+            for (name, value) in &settings.params {
+                let param = Param::parse(name).ok_or(format!("unknown parameter: {}", name))?;
+                device.execute(&Command::Set(param, *value))?;
+            }
+            device.execute(&Command::KeymapClear)?;
+            write_keymap(device, &settings)?;
+            device.execute(&Command::Save)?;
+        }
+        Action::Send { words } => {
+            let line = words.join(" ");
+            let command =
+                mickey_protocol::parse(&line).map_err(|error| format!("{}: {:?}", line, error))?;
+            for line in device.execute(&command)? {
+                println!("{}", line);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn read_enabled<T: Read + Write>(device: &mut Device<T>) -> Result<bool> {
+    let status = device.execute(&Command::Status)?;
+    let enabled = status
+        .iter()
+        .find_map(|line| line.strip_prefix("enabled: "))
+        .ok_or("status doesn't tell whether the device is enabled")?;
+    Ok(enabled == "true")
+}
+
+fn read_params<T: Read + Write>(device: &mut Device<T>) -> Result<BTreeMap<String, u32>> {
+    let output = device.execute(&Command::Get(None))?;
+    Ok(output
+        .iter()
+        .filter_map(|line| ParamValue::parse(line))
+        .map(|value| (value.param.name().to_string(), value.value))
+        .collect())
+}
+
+fn read_keymap<T: Read + Write>(device: &mut Device<T>) -> Result<BTreeMap<String, Code>> {
+    let output = device.execute(&Command::KeymapList)?;
+    Ok(output
+        .iter()
+        .filter_map(|line| KeymapEntry::parse(line))
+        .map(|entry| {
+            let code = Code {
+                protocol: entry.protocol.to_string(),
+                address: entry.address,
+                command: entry.command,
+            };
+            (entry.button.to_string(), code)
+        })
+        .collect())
+}
+
+fn write_keymap<T: Read + Write>(device: &mut Device<T>, settings: &SettingsFile) -> Result<()> {
+    for (button, code) in &settings.keymap {
+        let entry = KeymapEntry {
+            button,
+            protocol: &code.protocol,
+            address: code.address,
+            command: code.command,
+        };
+        device.execute(&Command::KeymapAdd(entry))?;
+    }
+    Ok(())
+}
+
+// Starts learning and follows its log. The device walks through the buttons on its own,
+// it can be aborted by a long press of its "Key".
+fn learn<T: Read + Write>(device: &mut Device<T>) -> Result<()> {
+    device.execute(&Command::Learn)?;
+    println!("press buttons of your remote control as they are listed, press Key to skip one");
+    for line in device.execute(&Command::Status)? {
+        if let Some(target) = line.strip_prefix("learning: ") {
+            println!("{}", target);
+        }
+    }
+
+    loop {
+        let line = match device.read_line() {
+            Ok(line) => line,
+            // no hurry, buttons are pressed by the user
+            Err(error) if error.kind() == io::ErrorKind::TimedOut => continue,
+            Err(error) => return Err(error.into()),
+        };
+        if line.starts_with("learned ") {
+            println!("{}", line);
+        } else if line == LEARNING_FINISHED || line == LEARNING_ABORTED {
+            println!("{}", line);
+            return Ok(());
+        }
+    }
+}
@@ -0,0 +1,23 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+// Code bound to a button, e.g. `Ok = { protocol = "Nec", address = 0, command = 28 }`.
+#[derive(Serialize, Deserialize)]
+pub struct Code {
+    pub protocol: String,
+    pub address: u32,
+    pub command: u32,
+}
+
+// Settings of the device in TOML. All the sections are optional,
+// a keymap file contains only `[keymap]`.
+#[derive(Serialize, Deserialize, Default)]
+pub struct SettingsFile {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub params: BTreeMap<String, u32>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub keymap: BTreeMap<String, Code>,
+}
@@ -0,0 +1,375 @@
+#![cfg(target_os = "linux")]
+
+// Runs the cli against the console of the firmware, emulated on the master side
+// of a pseudo terminal. Commands are executed by the console module of the firmware,
+// while the emulator plays the part of the USB serial port and the tasks around it.
+
+use std::ffi::CStr;
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use std::path::PathBuf;
+use std::process::{Command, Output, Stdio};
+use std::time::{Duration, Instant};
+
+use mickey_host_tests::config::MOUSE_DOUBLE_CLICK_DELAY_MS;
+use mickey_host_tests::console::{self, Status};
+use mickey_host_tests::decoder::{IrCode, Protocol};
+use mickey_host_tests::learning::Learning;
+use mickey_host_tests::mode::DeviceMode;
+use mickey_host_tests::output::ReportStats;
+use mickey_host_tests::remote::RcButton;
+use mickey_host_tests::settings::{Settings, Store};
+use mickey_host_tests::storage::{Storage, StorageError, BANKS};
+use mickey_protocol::{Param, LEARNING_FINISHED};
+
+// Input pauses for longer than this only while the cli waits for the log. During learning,
+// a button of the remote control is pressed whenever the input pauses.
+const IDLE_MS: i32 = 50;
+// The cli is killed if it runs longer, e.g. while waiting for a line that never comes.
+const DEADLINE: Duration = Duration::from_secs(20);
+
+const BANK_SIZE: usize = 4096;
+
+struct Ram {
+    banks: [[u8; BANK_SIZE]; BANKS],
+}
+
+impl Storage for Ram {
+    fn capacity(&self) -> usize {
+        BANK_SIZE
+    }
+
+    fn read(&self, bank: usize, offset: usize, bytes: &mut [u8]) {
+        bytes.copy_from_slice(&self.banks[bank][offset..offset + bytes.len()]);
+    }
+
+    fn write(&mut self, bank: usize, offset: usize, bytes: &[u8]) -> Result<(), StorageError> {
+        for (old, new) in self.banks[bank][offset..].iter_mut().zip(bytes) {
+            *old &= *new;
+        }
+        Ok(())
+    }
+
+    fn erase(&mut self, bank: usize) -> Result<(), StorageError> {
+        self.banks[bank].fill(0xff);
+        Ok(())
+    }
+}
+
+struct Firmware {
+    master: File,
+    // kept open, so that the master isn't hung up between runs of the cli
+    _slave: OwnedFd,
+    path: String,
+    line: Vec<u8>,
+    settings: Settings,
+    mode: DeviceMode,
+    learning: Option<Learning>,
+    learning_started: bool,
+    store: Store<Ram>,
+}
+
+impl Firmware {
+    fn new() -> Self {
+        let (mut master, mut slave) = (0, 0);
+        let mut name = [0; 64];
+        let path = unsafe {
+            let result = libc::openpty(
+                &mut master,
+                &mut slave,
+                name.as_mut_ptr(),
+                std::ptr::null(),
+                std::ptr::null(),
+            );
+            assert_eq!(result, 0, "pseudo terminal can't be opened");
+
+            // no echo or translation of line ends by the terminal itself
+            let mut termios = std::mem::zeroed();
+            libc::tcgetattr(slave, &mut termios);
+            libc::cfmakeraw(&mut termios);
+            libc::tcsetattr(slave, libc::TCSANOW, &termios);
+
+            CStr::from_ptr(name.as_ptr()).to_str().unwrap().to_string()
+        };
+
+        Firmware {
+            master: unsafe { File::from_raw_fd(master) },
+            _slave: unsafe { OwnedFd::from_raw_fd(slave) },
+            path,
+            line: Vec::new(),
+            settings: Settings::new(),
+            mode: DeviceMode::Mouse,
+            learning: None,
+            learning_started: false,
+            store: Store::new(Ram {
+                banks: [[0xff; BANK_SIZE]; BANKS],
+            }),
+        }
+    }
+
+    // Runs the cli with the arguments, serving the console until it exits.
+    fn cli(&mut self, args: &[&str]) -> Output {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_mickey-cli"))
+            .arg("--port")
+            .arg(&self.path)
+            .args(args)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+
+        let started = Instant::now();
+        while child.try_wait().unwrap().is_none() {
+            if started.elapsed() > DEADLINE {
+                child.kill().ok();
+                panic!("cli {:?} doesn't finish", args);
+            }
+            if self.wait_for_input(IDLE_MS) {
+                self.receive();
+            } else {
+                self.idle();
+            }
+        }
+        // anything left unread belongs to the finished run
+        while self.wait_for_input(0) && self.master.read(&mut [0; 256]).unwrap() > 0 {}
+        child.wait_with_output().unwrap()
+    }
+
+    // Runs the cli, which is expected to succeed, returns its output.
+    fn cli_ok(&mut self, args: &[&str]) -> String {
+        let output = self.cli(args);
+        assert!(
+            output.status.success(),
+            "cli {:?} failed: {}",
+            args,
+            String::from_utf8_lossy(&output.stderr)
+        );
+        String::from_utf8(output.stdout).unwrap()
+    }
+
+    fn wait_for_input(&self, timeout_ms: i32) -> bool {
+        let mut poll = libc::pollfd {
+            fd: self.master.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        unsafe { libc::poll(&mut poll, 1, timeout_ms) > 0 }
+    }
+
+    fn write(&mut self, text: &str) {
+        self.master.write_all(text.as_bytes()).unwrap();
+    }
+
+    fn log(&mut self, text: &str) {
+        self.write(&format!("{}\r\n", text));
+    }
+
+    // Echoes received characters and executes terminated lines, like `Console::read_line`.
+    fn receive(&mut self) {
+        let mut bytes = [0; 256];
+        let len = self.master.read(&mut bytes).unwrap();
+        for &byte in &bytes[..len] {
+            match byte {
+                b'\r' | b'\n' => {
+                    let line = std::mem::take(&mut self.line);
+                    if !line.is_empty() {
+                        self.write("\r\n");
+                        self.execute(&String::from_utf8(line).unwrap());
+                    }
+                }
+                byte => {
+                    self.line.push(byte);
+                    self.master.write_all(&[byte]).unwrap();
+                }
+            }
+        }
+    }
+
+    fn execute(&mut self, line: &str) {
+        let status = Status {
+            uptime_s: 1,
+            reports: ReportStats::default(),
+        };
+        let mut output = String::new();
+        console::execute(
+            &mut output,
+            line,
+            status,
+            &mut self.settings,
+            &mut self.mode,
+            &mut self.learning,
+            &mut self.store,
+        );
+        self.write(&output);
+    }
+
+    // Learning walks through the buttons while the cli follows the log,
+    // each of them gets a code of NEC, like `learning_task` and `frame_task` do.
+    fn idle(&mut self) {
+        let Some(session) = &mut self.learning else {
+            return;
+        };
+        if !self.learning_started {
+            self.learning_started = true;
+            self.log("learning started");
+            return;
+        }
+        match session.target() {
+            Some(target) => {
+                session.learn(&nec_code(target));
+                self.log(&format!("learned {:?}", target));
+            }
+            None => {
+                self.settings.button_map = *session.map();
+                self.learning = None;
+                self.learning_started = false;
+                self.log(LEARNING_FINISHED);
+                self.store.save(&self.settings).unwrap();
+            }
+        }
+    }
+}
+
+fn nec_code(button: RcButton) -> IrCode {
+    IrCode {
+        protocol: Protocol::Nec,
+        address: 0xff00,
+        command: button as u32,
+    }
+}
+
+fn temp_file(name: &str) -> PathBuf {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR"));
+    dir.join(format!("loopback-{}-{}", std::process::id(), name))
+}
+
+#[test]
+fn parameters_are_set_and_read() {
+    let mut firmware = Firmware::new();
+    firmware.cli_ok(&["send", "set", "sample_tolerance", "900"]);
+    assert_eq!(firmware.settings.get(Param::SampleTolerance), 900);
+    assert_eq!(
+        firmware.cli_ok(&["send", "get", "sample_tolerance"]),
+        "sample_tolerance = 900\n"
+    );
+
+    let output = firmware.cli(&["send", "set", "sample_interval", "0"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("invalid value"));
+
+    let status = firmware.cli_ok(&["status"]);
+    assert!(status.contains("enabled: true\n"), "{}", status);
+    assert!(status.contains("learning: no\n"), "{}", status);
+    assert!(status.contains("sample_tolerance = 900\n"), "{}", status);
+    assert!(status.contains("sample_interval = 2150\n"), "{}", status);
+}
+
+#[test]
+fn keymap_is_loaded_and_dumped() {
+    let mut firmware = Firmware::new();
+    let file = temp_file("keymap.toml");
+    fs::write(
+        &file,
+        "[keymap]\n\
+         Ok = { protocol = \"Nec\", address = 0xff00, command = 0x1c }\n\
+         Up = { protocol = \"rc5\", address = 0, command = 0x20 }\n",
+    )
+    .unwrap();
+    firmware.cli_ok(&["load-keymap", file.to_str().unwrap()]);
+
+    let ok = IrCode {
+        protocol: Protocol::Nec,
+        address: 0xff00,
+        command: 0x1c,
+    };
+    let up = IrCode {
+        protocol: Protocol::Rc5,
+        address: 0,
+        command: 0x20,
+    };
+    let saved = firmware.store.load().unwrap().button_map;
+    for map in [firmware.settings.button_map, saved] {
+        assert!(map.get(RcButton::Ok) == Some(ok));
+        assert!(map.get(RcButton::Up) == Some(up));
+        assert!(map.get(RcButton::Down).is_none());
+    }
+
+    firmware.cli_ok(&["dump-keymap", file.to_str().unwrap()]);
+    let dumped: toml::Table = fs::read_to_string(&file).unwrap().parse().unwrap();
+    let keymap = dumped["keymap"].as_table().unwrap();
+    assert_eq!(keymap.len(), 2);
+    assert_eq!(keymap["Ok"]["protocol"].as_str(), Some("Nec"));
+    assert_eq!(keymap["Ok"]["address"].as_integer(), Some(0xff00));
+    assert_eq!(keymap["Ok"]["command"].as_integer(), Some(0x1c));
+    assert_eq!(keymap["Up"]["protocol"].as_str(), Some("Rc5"));
+
+    fs::write(
+        &file,
+        "[keymap]\nDown = { protocol = \"Sirc\", address = 1, command = 0x33 }\n",
+    )
+    .unwrap();
+    firmware.cli_ok(&["load-keymap", "--replace", file.to_str().unwrap()]);
+    let map = firmware.store.load().unwrap().button_map;
+    assert!(map.get(RcButton::Ok).is_none());
+    assert!(map.get(RcButton::Down).is_some());
+
+    fs::remove_file(file).ok();
+}
+
+#[test]
+fn settings_are_backed_up_and_restored() {
+    let mut firmware = Firmware::new();
+    firmware.cli_ok(&["send", "set", "double_click_delay", "90"]);
+    firmware.cli_ok(&["send", "disable"]);
+    firmware.cli_ok(&["send", "keymap", "add", "Ok", "Nec", "0xff00", "0x1c"]);
+    let file = temp_file("settings.toml");
+    firmware.cli_ok(&["backup", file.to_str().unwrap()]);
+
+    firmware.cli_ok(&["send", "defaults"]);
+    assert_eq!(
+        firmware.settings.get(Param::DoubleClickDelay),
+        MOUSE_DOUBLE_CLICK_DELAY_MS
+    );
+    assert!(firmware.settings.enabled);
+    assert!(firmware.store.load().is_none());
+
+    firmware.cli_ok(&["restore", file.to_str().unwrap()]);
+    let saved = firmware.store.load().unwrap();
+    for settings in [firmware.settings, saved] {
+        assert_eq!(settings.get(Param::DoubleClickDelay), 90);
+        assert!(!settings.enabled);
+        let ok = IrCode {
+            protocol: Protocol::Nec,
+            address: 0xff00,
+            command: 0x1c,
+        };
+        assert!(settings.button_map.get(RcButton::Ok) == Some(ok));
+    }
+
+    // console reads the saved settings back
+    firmware.cli_ok(&["send", "defaults"]);
+    firmware.cli_ok(&["send", "restore"]);
+    assert_eq!(firmware.settings.get(Param::DoubleClickDelay), 90);
+
+    fs::remove_file(file).ok();
+}
+
+#[test]
+fn learning_is_followed_to_the_end() {
+    let mut firmware = Firmware::new();
+    let output = firmware.cli_ok(&["learn"]);
+
+    let mut lines = output.lines().skip(1);
+    assert_eq!(lines.next(), Some("Up"));
+    for button in RcButton::ALL {
+        assert_eq!(lines.next(), Some(format!("learned {:?}", button).as_str()));
+    }
+    assert_eq!(lines.next(), Some(LEARNING_FINISHED));
+    assert_eq!(lines.next(), None);
+
+    let saved = firmware.store.load().unwrap().button_map;
+    for button in RcButton::ALL {
+        assert!(saved.get(button) == Some(nec_code(button)));
+    }
+}
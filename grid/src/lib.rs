@@ -0,0 +1,122 @@
+#![no_std]
+
+// Grid of the absolute pointer mode. The screen is split into GRID_SIZE x GRID_SIZE cells,
+// jumping to one of them splits that cell again, so two jumps reach one of 9x9 cells.
+// Arrows refine the position within the current cell, keynav-style. It doesn't depend
+// on the firmware, so it's a crate of its own, tested on the host.
+
+pub const GRID_SIZE: u32 = 3;
+// Positions range over 0..EXTENT on both axes, i.e. over the logical range of the report.
+pub const EXTENT: u32 = 1 << 16;
+// Refining moves the pointer by this fraction of the cell.
+pub const REFINE_STEPS: u32 = 8;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Cell {
+    pub left: u32,
+    pub top: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Cell {
+    pub const SCREEN: Cell = Cell {
+        left: 0,
+        top: 0,
+        width: EXTENT,
+        height: EXTENT,
+    };
+
+    // Cell of the grid laid over this one, `None` if the index is out of the grid.
+    // Cells are never narrower than a single position.
+    pub fn split(&self, index: usize) -> Option<Cell> {
+        let cells = (GRID_SIZE * GRID_SIZE) as usize;
+        if index >= cells {
+            return None;
+        }
+        let column = index as u32 % GRID_SIZE;
+        let row = index as u32 / GRID_SIZE;
+        let (left, width) = split_span(self.left, self.width, column);
+        let (top, height) = split_span(self.top, self.height, row);
+        Some(Cell {
+            left,
+            top,
+            width,
+            height,
+        })
+    }
+
+    pub fn center(&self) -> (u32, u32) {
+        (self.left + self.width / 2, self.top + self.height / 2)
+    }
+}
+
+// Start and length of the part of the span, which is divided into GRID_SIZE parts.
+fn split_span(start: u32, len: u32, part: u32) -> (u32, u32) {
+    let from = start + len * part / GRID_SIZE;
+    let to = start + len * (part + 1) / GRID_SIZE;
+    (from, (to - from).max(1))
+}
+
+// Moves the position by `steps` of the span, keeping it within the span.
+fn refine_span(position: u32, start: u32, len: u32, steps: i8) -> u32 {
+    let step = (len / REFINE_STEPS).max(1) as i64;
+    let moved = i64::from(position) + i64::from(steps) * step;
+    moved.clamp(i64::from(start), i64::from(start + len - 1)) as u32
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Grid {
+    cell: Cell,
+    x: u32,
+    y: u32,
+}
+
+impl Grid {
+    // Whole screen, pointer in its center.
+    pub const fn new() -> Self {
+        Grid {
+            cell: Cell::SCREEN,
+            x: EXTENT / 2,
+            y: EXTENT / 2,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+
+    // Narrows the grid down to the cell and puts the pointer in its center.
+    // Returns `false` if there is no such cell.
+    pub fn jump(&mut self, index: usize) -> bool {
+        let Some(cell) = self.cell.split(index) else {
+            return false;
+        };
+        self.cell = cell;
+        (self.x, self.y) = cell.center();
+        true
+    }
+
+    // Moves the pointer within the current cell.
+    pub fn refine(&mut self, x: i8, y: i8) {
+        let cell = self.cell;
+        self.x = refine_span(self.x, cell.left, cell.width, x);
+        self.y = refine_span(self.y, cell.top, cell.height, y);
+    }
+
+    // Cell the pointer is confined to.
+    pub fn cell(&self) -> Cell {
+        self.cell
+    }
+
+    // Position of the pointer, as sent to the host.
+    pub fn position(&self) -> (u16, u16) {
+        (self.x as u16, self.y as u16)
+    }
+}
+
+impl Default for Grid {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,83 @@
+use mickey_grid::{Cell, Grid, EXTENT, GRID_SIZE, REFINE_STEPS};
+
+#[test]
+fn starts_in_the_center_of_the_screen() {
+    let grid = Grid::new();
+    assert_eq!(grid.cell(), Cell::SCREEN);
+    assert_eq!(grid.position(), (0x8000, 0x8000));
+}
+
+#[test]
+fn jumps_to_the_center_of_the_cell() {
+    let mut grid = Grid::new();
+    assert!(grid.jump(0));
+    let third = EXTENT / GRID_SIZE;
+    assert_eq!(grid.position(), ((third / 2) as u16, (third / 2) as u16));
+
+    let mut grid = Grid::new();
+    assert!(grid.jump(5));
+    let cell = grid.cell();
+    assert_eq!((cell.left, cell.top), (2 * EXTENT / 3, EXTENT / 3));
+    assert_eq!(cell.left + cell.width, EXTENT);
+}
+
+#[test]
+fn second_jump_reaches_a_cell_of_9x9_grid() {
+    let mut grid = Grid::new();
+    assert!(grid.jump(8));
+    assert!(grid.jump(8));
+    let cell = grid.cell();
+    assert_eq!(cell.left + cell.width, EXTENT);
+    assert_eq!(cell.top + cell.height, EXTENT);
+    assert!(cell.width.abs_diff(EXTENT / 9) <= 1);
+    assert!(cell.height.abs_diff(EXTENT / 9) <= 1);
+}
+
+#[test]
+fn rejects_cells_out_of_the_grid() {
+    let mut grid = Grid::new();
+    assert!(!grid.jump((GRID_SIZE * GRID_SIZE) as usize));
+    assert_eq!(grid, Grid::new());
+}
+
+#[test]
+fn cells_never_vanish() {
+    let mut grid = Grid::new();
+    for _ in 0..20 {
+        assert!(grid.jump(4));
+    }
+    let cell = grid.cell();
+    assert!(cell.width >= 1 && cell.height >= 1);
+    let (x, y) = grid.position();
+    assert_eq!((u32::from(x), u32::from(y)), (cell.left, cell.top));
+}
+
+#[test]
+fn refines_within_the_cell() {
+    let mut grid = Grid::new();
+    assert!(grid.jump(4));
+    let cell = grid.cell();
+    let (x, y) = grid.position();
+
+    grid.refine(1, -1);
+    let step = cell.width / REFINE_STEPS;
+    assert_eq!(
+        grid.position(),
+        ((u32::from(x) + step) as u16, (u32::from(y) - step) as u16)
+    );
+
+    grid.refine(-100, 100);
+    assert_eq!(
+        grid.position(),
+        (cell.left as u16, (cell.top + cell.height - 1) as u16)
+    );
+}
+
+#[test]
+fn reset_brings_back_the_whole_screen() {
+    let mut grid = Grid::new();
+    assert!(grid.jump(2));
+    grid.refine(3, 3);
+    grid.reset();
+    assert_eq!(grid, Grid::new());
+}
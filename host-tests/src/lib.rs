@@ -0,0 +1,47 @@
+// Modules of the firmware which don't depend on the hardware, compiled for the host
+// to be tested by `cargo test`.
+
+#[path = "../../src/acceleration.rs"]
+pub mod acceleration;
+#[path = "../../src/config.rs"]
+pub mod config;
+#[path = "../../src/console.rs"]
+pub mod console;
+#[path = "../../src/consumer.rs"]
+pub mod consumer;
+#[path = "../../src/decoder.rs"]
+pub mod decoder;
+#[path = "../../src/gamepad.rs"]
+pub mod gamepad;
+#[path = "../../src/ir.rs"]
+pub mod ir;
+#[path = "../../src/keyboard.rs"]
+pub mod keyboard;
+#[path = "../../src/keymap.rs"]
+pub mod keymap;
+#[path = "../../src/learning.rs"]
+pub mod learning;
+#[path = "../../src/mode.rs"]
+pub mod mode;
+#[path = "../../src/motion.rs"]
+pub mod motion;
+#[path = "../../src/mouse.rs"]
+pub mod mouse;
+#[path = "../../src/nec.rs"]
+pub mod nec;
+#[path = "../../src/output.rs"]
+pub mod output;
+#[path = "../../src/rc5.rs"]
+pub mod rc5;
+#[path = "../../src/rc6.rs"]
+pub mod rc6;
+#[path = "../../src/remote.rs"]
+pub mod remote;
+#[path = "../../src/reports.rs"]
+pub mod reports;
+#[path = "../../src/settings.rs"]
+pub mod settings;
+#[path = "../../src/sirc.rs"]
+pub mod sirc;
+#[path = "../../src/storage.rs"]
+pub mod storage;
@@ -0,0 +1,193 @@
+// Checks that each report, as serialized by the firmware, matches its layout
+// in the descriptor assembled by the build script.
+
+use std::collections::BTreeSet;
+
+use mickey_host_tests::reports::{self, *};
+use mickey_protocol::CONFIG_REPORT_SIZE;
+use usbd_hid::descriptor::{AsInputReport, SerializedDescriptor};
+
+#[path = "../../build/assemble.rs"]
+mod assemble;
+
+use assemble::*;
+
+// items which only the layout of the reports needs
+const OUTPUT: u8 = 0x9;
+const REPORT_SIZE: u8 = 0x7;
+const REPORT_COUNT: u8 = 0x9;
+
+// Main item of a report: offset and length in bits, excluding the report ID.
+#[derive(Clone, Copy, Debug)]
+struct Field {
+    id: u8,
+    kind: u8,
+    offset: usize,
+    len: usize,
+}
+
+// Lays out input, output and feature items of the descriptor.
+fn parse(descriptor: &[u8]) -> Vec<Field> {
+    let mut fields: Vec<Field> = Vec::new();
+    let (mut id, mut size, mut count) = (0, 0, 0);
+    for (prefix, value, _) in items(descriptor) {
+        match item_type(prefix) {
+            (GLOBAL_ITEM, REPORT_ID) => id = value as u8,
+            (GLOBAL_ITEM, REPORT_SIZE) => size = value,
+            (GLOBAL_ITEM, REPORT_COUNT) => count = value,
+            (MAIN_ITEM, kind @ (INPUT | OUTPUT | FEATURE)) => {
+                let offset = fields
+                    .iter()
+                    .filter(|field| field.id == id && field.kind == kind)
+                    .map(|field| field.len)
+                    .sum();
+                let len = size * count;
+                fields.push(Field {
+                    id,
+                    kind,
+                    offset,
+                    len,
+                });
+            }
+            _ => {}
+        }
+    }
+    fields
+}
+
+fn report_ids(descriptor: &[u8]) -> BTreeSet<u8> {
+    parse(descriptor).iter().map(|field| field.id).collect()
+}
+
+// Serializes the report and compares it with its descriptor. Fields of the report are given
+// in the order of their input items, each of them should be found where its item is.
+fn check_report<R: ReportId + AsInputReport + SerializedDescriptor>(
+    name: &str,
+    report: R,
+    expected: &[&[u8]],
+) {
+    let mut buf = [0; 64];
+    let len = ssmarshal::serialize(&mut buf, &Tagged::new(report)).unwrap();
+    let (serialized_id, payload) = buf[..len].split_first().unwrap();
+
+    let descriptor = descriptor::<R>();
+    assert_eq!(
+        report_ids(&descriptor),
+        BTreeSet::from([R::ID]),
+        "{}: report ID",
+        name
+    );
+    assert_eq!(*serialized_id, R::ID, "{}: serialized report ID", name);
+
+    let inputs: Vec<Field> = parse(&descriptor)
+        .into_iter()
+        .filter(|field| field.kind == INPUT)
+        .collect();
+    let bits: usize = inputs.iter().map(|field| field.len).sum();
+    assert_eq!(payload.len() * 8, bits, "{}: size", name);
+    assert_eq!(inputs.len(), expected.len(), "{}: number of fields", name);
+
+    for (index, (field, value)) in inputs.iter().zip(expected).enumerate() {
+        assert!(
+            field.offset % 8 == 0 && field.len % 8 == 0,
+            "{}: field {} isn't aligned",
+            name,
+            index
+        );
+        let bytes = &payload[field.offset / 8..(field.offset + field.len) / 8];
+        assert_eq!(
+            bytes, *value,
+            "{}: field {} at bit {}",
+            name, index, field.offset
+        );
+    }
+}
+
+#[test]
+fn mouse_report() {
+    // distinct value in each field, to be found at the offset given by the descriptor
+    let mouse = MouseReportEx {
+        buttons: 0x11,
+        x: 0x12,
+        y: 0x13,
+        wheel: 0x14,
+        pan: 0x15,
+        wheel_multiplier: 0x16,
+        pan_multiplier: 0x17,
+    };
+    check_report(
+        "MouseReportEx",
+        mouse,
+        &[&[0x11], &[0x12], &[0x13], &[0x14], &[0x15]],
+    );
+
+    // feature report of the mouse is a byte for each multiplier, see `ResolutionMultiplier`
+    let multipliers: Vec<usize> = parse(&descriptor::<MouseReportEx>())
+        .iter()
+        .filter(|field| field.kind == FEATURE)
+        .map(|field| field.len)
+        .collect();
+    assert_eq!(multipliers, [8, 8], "MouseReportEx: resolution multipliers");
+}
+
+#[test]
+fn absolute_pointer_report() {
+    let absolute = AbsolutePointerReportEx {
+        buttons: 0x41,
+        x: 0x4342,
+        y: 0x4544,
+    };
+    check_report(
+        "AbsolutePointerReportEx",
+        absolute,
+        &[&[0x41], &[0x42, 0x43], &[0x44, 0x45]],
+    );
+}
+
+#[test]
+fn gamepad_report() {
+    let gamepad = GamepadReportEx {
+        buttons: [0x51, 0x52],
+        hat: 0x53,
+    };
+    check_report("GamepadReportEx", gamepad, &[&[0x51, 0x52], &[0x53]]);
+}
+
+#[test]
+fn keyboard_reports() {
+    let keyboard = KeyboardReportEx {
+        modifier: 0x21,
+        reserved: 0x22,
+        leds: 0x23,
+        keycodes: [0x24, 0x25, 0x26, 0x27, 0x28, 0x29],
+    };
+    check_report(
+        "KeyboardReportEx",
+        keyboard,
+        &[&[0x21], &[0x22], &[0x24, 0x25, 0x26, 0x27, 0x28, 0x29]],
+    );
+
+    let media = MediaKeyboardReportEx { usage_id: 0x3132 };
+    check_report("MediaKeyboardReportEx", media, &[&[0x32, 0x31]]);
+}
+
+#[test]
+fn config_report() {
+    // configuration report is exchanged as a whole, see mickey-protocol
+    let bits: usize = parse(&descriptor::<ConfigReportEx>())
+        .iter()
+        .filter(|field| field.kind == FEATURE)
+        .map(|field| field.len)
+        .sum();
+    assert_eq!(bits, (CONFIG_REPORT_SIZE - 1) * 8, "ConfigReportEx: size");
+}
+
+#[test]
+fn report_ids_are_unique() {
+    let descriptors = descriptors();
+    let ids: BTreeSet<u8> = descriptors
+        .iter()
+        .flat_map(|desc| report_ids(desc))
+        .collect();
+    assert_eq!(ids.len(), descriptors.len(), "report IDs must be unique");
+}
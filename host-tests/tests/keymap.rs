@@ -0,0 +1,162 @@
+use mickey_host_tests::decoder::EventKind;
+use mickey_host_tests::keymap::{self, Action, ClickKind, State, KEYMAP};
+use mickey_host_tests::mode::DeviceMode;
+use mickey_host_tests::motion::Motion;
+use mickey_host_tests::mouse::LEFT_BUTTON;
+use mickey_host_tests::output::{Report, Steps};
+use mickey_host_tests::remote::RcButton;
+use mickey_host_tests::settings::Settings;
+use mickey_protocol::Param;
+use usbd_hid::descriptor::KeyboardUsage;
+
+// Modes the button switches to from the mode.
+fn switches(from: DeviceMode) -> impl Iterator<Item = DeviceMode> {
+    KEYMAP
+        .iter()
+        .filter_map(move |&(mode, _, action)| match action {
+            Action::SwitchMode(to) if mode == from => Some(to),
+            _ => None,
+        })
+}
+
+#[test]
+fn buttons_are_bound_once_per_mode() {
+    for (index, (mode, button, _)) in KEYMAP.iter().enumerate() {
+        let duplicate = KEYMAP[index + 1..]
+            .iter()
+            .any(|(other_mode, other_button, _)| other_mode == mode && other_button == button);
+        assert!(!duplicate, "{:?} is bound twice in {:?} mode", button, mode);
+    }
+}
+
+#[test]
+fn every_mode_can_be_left() {
+    for mode in DeviceMode::ALL {
+        assert!(
+            switches(mode).any(|to| to != mode),
+            "{:?} mode doesn't switch to another mode",
+            mode
+        );
+    }
+}
+
+#[test]
+fn every_mode_can_be_reached() {
+    // device starts in the mouse mode
+    let mut reached = vec![DeviceMode::Mouse];
+    let mut index = 0;
+    while let Some(&from) = reached.get(index) {
+        for to in switches(from) {
+            if !reached.contains(&to) {
+                reached.push(to);
+            }
+        }
+        index += 1;
+    }
+
+    for mode in DeviceMode::ALL {
+        assert!(reached.contains(&mode), "{:?} mode can't be reached", mode);
+    }
+}
+
+// Relative move of the pointer, without any buttons.
+fn mouse(x: i8, y: i8) -> Report {
+    Report::Mouse {
+        buttons: 0,
+        x,
+        y,
+        wheel: 0,
+        pan: 0,
+    }
+}
+
+// Delays [ms] and reports scheduled on press of the button bound to the action.
+fn press(action: &Action) -> Vec<(u32, Report)> {
+    press_in(DeviceMode::Mouse, action)
+}
+
+fn press_in(mode: DeviceMode, action: &Action) -> Vec<(u32, Report)> {
+    let mut state = State::new();
+    state.set_mode(mode);
+    let mut settings = Settings::new();
+    assert!(settings.set(Param::ButtonReleaseDelay, 30));
+    assert!(settings.set(Param::DoubleClickDelay, 70));
+    let mut steps = Steps::new();
+    keymap::run(
+        action,
+        EventKind::Press,
+        0,
+        &settings,
+        &mut state,
+        &mut Motion::new(),
+        &mut steps,
+    );
+    steps
+        .as_slice()
+        .iter()
+        .map(|step| (step.delay_ms, step.report))
+        .collect()
+}
+
+#[test]
+fn clicks_and_macros_share_timing() {
+    // held for the release delay, repeated after the double click delay
+    let clicks = press(&Action::Click(ClickKind::Double, LEFT_BUTTON));
+    assert!(
+        clicks
+            == [
+                (0, Report::buttons(LEFT_BUTTON)),
+                (30, Report::buttons(0)),
+                (70, Report::buttons(LEFT_BUTTON)),
+                (30, Report::buttons(0)),
+            ]
+    );
+
+    const KEYS: [Action; 2] = [
+        Action::Key(KeyboardUsage::KeyboardAa),
+        Action::Key(KeyboardUsage::KeyboardBb),
+    ];
+    let taps = press(&Action::Macro(&KEYS));
+    assert!(
+        taps == [
+            (0, Report::Key(KeyboardUsage::KeyboardAa)),
+            (30, Report::ReleaseKeys),
+            (70, Report::Key(KeyboardUsage::KeyboardBb)),
+            (30, Report::ReleaseKeys),
+        ]
+    );
+}
+
+#[test]
+fn macros_move_the_pointer() {
+    // 30 ms at the minimum speed of 12/16 px every 8 ms, i.e. 3 intervals
+    const MOVES: [Action; 2] = [Action::Move { x: 1, y: 0 }, Action::Move { x: -1, y: 1 }];
+    let moves = press(&Action::Macro(&MOVES));
+    assert!(moves == [(0, mouse(2, 0)), (70, mouse(-2, 2)),]);
+}
+
+#[test]
+fn absolute_clicks_go_with_the_pointer() {
+    // pointer starts in the center of the screen
+    let absolute = |buttons| Report::Absolute {
+        buttons,
+        x: 0x8000,
+        y: 0x8000,
+    };
+    let click = press_in(
+        DeviceMode::Absolute,
+        &Action::Click(ClickKind::Hold, LEFT_BUTTON),
+    );
+    assert!(click == [(0, absolute(LEFT_BUTTON))]);
+
+    let action = keymap::lookup(DeviceMode::Absolute, RcButton::Pause).unwrap();
+    assert!(
+        press_in(DeviceMode::Absolute, action)
+            == [
+                (0, absolute(LEFT_BUTTON)),
+                (30, absolute(0)),
+                (70, absolute(LEFT_BUTTON)),
+                (30, absolute(0)),
+            ]
+    );
+}
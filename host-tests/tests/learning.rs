@@ -0,0 +1,46 @@
+use mickey_host_tests::config::LEARNING_HOLD;
+use mickey_host_tests::keymap::{self, Action};
+use mickey_host_tests::learning::is_learning_hold;
+use mickey_host_tests::mode::DeviceMode;
+use mickey_host_tests::remote::RcButton;
+
+#[test]
+fn holding_stop_starts_learning() {
+    for mode in [
+        DeviceMode::Mouse,
+        DeviceMode::Keyboard,
+        DeviceMode::Absolute,
+    ] {
+        assert!(
+            is_learning_hold(mode, RcButton::Stop, LEARNING_HOLD),
+            "{:?}",
+            mode
+        );
+        assert!(
+            !is_learning_hold(mode, RcButton::Stop, LEARNING_HOLD - 1),
+            "{:?}",
+            mode
+        );
+        assert!(
+            !is_learning_hold(mode, RcButton::Play, LEARNING_HOLD),
+            "{:?}",
+            mode
+        );
+    }
+}
+
+#[test]
+fn held_gamepad_buttons_dont_start_learning() {
+    // Stop is a trigger, which games hold for long
+    assert!(matches!(
+        keymap::lookup(DeviceMode::Gamepad, RcButton::Stop),
+        Some(Action::GamepadButton(_))
+    ));
+    for button in RcButton::ALL {
+        assert!(
+            !is_learning_hold(DeviceMode::Gamepad, button, 10 * LEARNING_HOLD),
+            "{:?}",
+            button
+        );
+    }
+}
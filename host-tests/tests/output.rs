@@ -0,0 +1,106 @@
+use mickey_host_tests::config::REPORT_QUEUE_LEN;
+use mickey_host_tests::output::{Report, ReportQueue};
+use usb_device::UsbError;
+
+const MOVE: Report = Report::Mouse {
+    buttons: 0,
+    x: 1,
+    y: 0,
+    wheel: 0,
+    pan: 0,
+};
+
+// Sends all the queued reports, returns them in the order they were sent.
+fn sent(queue: &mut ReportQueue) -> Vec<Report> {
+    let mut sent = Vec::new();
+    queue.flush_with(|report| {
+        sent.push(*report);
+        Ok(1)
+    });
+    sent
+}
+
+#[test]
+fn reports_wait_while_the_endpoint_is_busy() {
+    let mut queue = ReportQueue::new();
+    queue.push(Report::buttons(1));
+    queue.push(Report::buttons(0));
+    queue.flush_with(|_| Err(UsbError::WouldBlock));
+    assert_eq!(queue.stats().blocked, 1);
+
+    assert!(sent(&mut queue) == [Report::buttons(1), Report::buttons(0)]);
+    assert_eq!(queue.stats().sent, 2);
+    assert!(sent(&mut queue).is_empty());
+}
+
+#[test]
+fn release_replaces_latest_move() {
+    let mut queue = ReportQueue::new();
+    for _ in 0..REPORT_QUEUE_LEN {
+        queue.push(MOVE);
+    }
+    queue.push(MOVE);
+    queue.push(Report::ReleaseKeys);
+
+    let sent = sent(&mut queue);
+    assert_eq!(sent.len(), REPORT_QUEUE_LEN);
+    assert!(sent[REPORT_QUEUE_LEN - 1] == Report::ReleaseKeys);
+    assert_eq!(queue.stats().dropped, 2);
+}
+
+#[test]
+fn release_survives_queue_full_of_clicks() {
+    let mut queue = ReportQueue::new();
+    for index in 0..REPORT_QUEUE_LEN {
+        queue.push(Report::buttons((index as u8 + 1) % 2));
+    }
+    queue.push(Report::ReleaseKeys);
+    queue.push(Report::ReleaseGamepad);
+
+    let sent = sent(&mut queue);
+    assert_eq!(sent.len(), REPORT_QUEUE_LEN);
+    assert!(sent[REPORT_QUEUE_LEN - 2..] == [Report::ReleaseKeys, Report::ReleaseGamepad]);
+    // mouse ends up with its buttons released, as the last click left it
+    let mouse = sent
+        .iter()
+        .rfind(|report| matches!(report, Report::Mouse { .. }));
+    assert!(mouse == Some(&Report::buttons(0)));
+}
+
+#[test]
+fn last_release_of_its_kind_survives() {
+    let mut queue = ReportQueue::new();
+    for index in 0..REPORT_QUEUE_LEN {
+        let report = match index % 4 {
+            0 => Report::ReleaseKeys,
+            1 => Report::ReleaseConsumer,
+            2 => Report::ReleaseGamepad,
+            _ => Report::buttons(0),
+        };
+        queue.push(report);
+    }
+    queue.push(Report::buttons(1));
+    queue.push(Report::buttons(0));
+
+    let sent = sent(&mut queue);
+    assert!(sent[REPORT_QUEUE_LEN - 2..] == [Report::buttons(1), Report::buttons(0)]);
+    for release in [
+        Report::ReleaseKeys,
+        Report::ReleaseConsumer,
+        Report::ReleaseGamepad,
+    ] {
+        assert!(sent.contains(&release));
+    }
+}
+
+#[test]
+fn ordinary_report_is_dropped_when_full() {
+    let mut queue = ReportQueue::new();
+    for _ in 0..REPORT_QUEUE_LEN {
+        queue.push(Report::buttons(0));
+    }
+    queue.push(MOVE);
+
+    assert!(!sent(&mut queue).contains(&MOVE));
+    assert_eq!(queue.stats().dropped, 1);
+}
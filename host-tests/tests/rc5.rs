@@ -0,0 +1,73 @@
+use mickey_host_tests::decoder::{self, Decoder, EventKind, Protocol};
+use mickey_host_tests::ir::Frame;
+use mickey_host_tests::rc5;
+use mickey_host_tests::remote::Sampling;
+
+const UNIT: u32 = 889;
+
+// Captured frame of RC5: marks and spaces [us] of the bits, MSB first. The first half
+// of the start bit and the trailing space are lost in the idle state.
+fn frame(toggle: bool, address: u8, command: u8) -> Frame {
+    let field = command & 0x40 == 0;
+    let data = 1 << 13
+        | u16::from(field) << 12
+        | u16::from(toggle) << 11
+        | u16::from(address & 0x1f) << 6
+        | u16::from(command & 0x3f);
+    // 1 is a space followed by a mark, 0 a mark followed by a space
+    let halves = (0..14).rev().flat_map(|bit| {
+        let one = data & 1 << bit != 0;
+        [!one, one]
+    });
+
+    let mut frame = Frame::new();
+    let mut run = 0;
+    let mut mark = true;
+    for half in halves.skip(1) {
+        if half != mark {
+            frame.push(run);
+            run = 0;
+            mark = half;
+        }
+        run += UNIT;
+    }
+    if mark {
+        frame.push(run);
+    }
+    frame
+}
+
+#[test]
+fn frames_are_decoded() {
+    let decoded = rc5::decode_frame(&frame(true, 0x05, 0x35)).unwrap();
+    assert_eq!(
+        (decoded.address, decoded.command, decoded.toggle),
+        (0x05, 0x35, true)
+    );
+    let decoded = rc5::decode_frame(&frame(false, 0x1f, 0x7f)).unwrap();
+    assert_eq!(
+        (decoded.address, decoded.command, decoded.toggle),
+        (0x1f, 0x7f, false)
+    );
+}
+
+#[test]
+fn frames_with_uniform_spaces_are_not_dropped() {
+    // after the leader, all spaces last one unit and a single mark lasts two,
+    // which looks like pulse width coding
+    let sampling = Sampling::new();
+    let mut decoder = Decoder::new();
+    for toggle in [false, true] {
+        let frame = frame(toggle, 0, 0);
+        assert_eq!(decoder::classify(&frame, &sampling), Some(Protocol::Rc5));
+        let decoded = rc5::decode_frame(&frame).unwrap();
+        assert_eq!((decoded.address, decoded.command), (0, 0));
+
+        let event = decoder.decode(&frame, 0, &sampling).unwrap();
+        assert_eq!(event.kind, EventKind::Press);
+        assert_eq!(
+            (event.code.protocol, event.code.address, event.code.command),
+            (Protocol::Rc5, 0, 0)
+        );
+    }
+}
@@ -0,0 +1,49 @@
+use mickey_host_tests::config::MAX_SAMPLING_US;
+use mickey_host_tests::ir::{Frame, Timing};
+use mickey_host_tests::remote::{self, Sampling};
+
+fn frame(durations: &[u32]) -> Frame {
+    let mut frame = Frame::new();
+    for &duration in durations {
+        frame.push(duration);
+    }
+    frame
+}
+
+#[test]
+fn default_frame_is_decoded() {
+    // preamble, then 1 (space) in two slots, 0 (mark) in one, 1 in the rest
+    let sampling = Sampling::new();
+    let lead_in = sampling.offset_us - sampling.interval_us / 2;
+    let frame = frame(&[17_400, lead_in + 2 * 2150, 2150]);
+    let all = (1 << remote::DATA_WIDTH) - 1;
+    assert_eq!(
+        remote::decode_frame(&frame, &sampling),
+        Some(all & !(1 << (remote::DATA_WIDTH - 3)))
+    );
+}
+
+#[test]
+fn extreme_timing_doesnt_overflow() {
+    let widest = Sampling {
+        preamble: Timing::new(MAX_SAMPLING_US, MAX_SAMPLING_US),
+        offset_us: MAX_SAMPLING_US,
+        interval_us: MAX_SAMPLING_US,
+        tolerance_us: MAX_SAMPLING_US,
+    };
+    let narrowest = Sampling {
+        preamble: Timing::new(MAX_SAMPLING_US, MAX_SAMPLING_US),
+        offset_us: 0,
+        interval_us: 1,
+        tolerance_us: 0,
+    };
+    for sampling in [widest, narrowest] {
+        for durations in [
+            &[MAX_SAMPLING_US, u32::MAX][..],
+            &[MAX_SAMPLING_US, u32::MAX, u32::MAX, u32::MAX],
+            &[MAX_SAMPLING_US, 1, u32::MAX],
+        ] {
+            remote::decode_frame(&frame(durations), &sampling);
+        }
+    }
+}
@@ -0,0 +1,215 @@
+use mickey_host_tests::config::{MAX_ACCELERATION_TIME_MS, MAX_CLICK_DELAY_MS, MAX_SAMPLING_US};
+use mickey_host_tests::settings::{Settings, Store};
+use mickey_host_tests::storage::{Storage, StorageError, BANKS};
+use mickey_protocol::Param;
+
+// four slots of a record in each bank
+const BANK_SIZE: usize = 2048;
+const SLOTS: u32 = 4;
+
+// Banks of NOR flash kept in RAM. Power can be cut after a number of bytes
+// has been programmed or erased, leaving the rest of the operation undone.
+struct Flash {
+    banks: [[u8; BANK_SIZE]; BANKS],
+    // bytes left until the power fails, `None` if it doesn't
+    power: Option<usize>,
+}
+
+impl Flash {
+    fn new() -> Self {
+        Flash {
+            banks: [[0xff; BANK_SIZE]; BANKS],
+            power: None,
+        }
+    }
+
+    // Number of bytes which can be processed before the power fails.
+    fn budget(&mut self, len: usize) -> usize {
+        match &mut self.power {
+            Some(left) => {
+                let done = len.min(*left);
+                *left -= done;
+                done
+            }
+            None => len,
+        }
+    }
+}
+
+impl Storage for &mut Flash {
+    fn capacity(&self) -> usize {
+        BANK_SIZE
+    }
+
+    fn read(&self, bank: usize, offset: usize, bytes: &mut [u8]) {
+        bytes.copy_from_slice(&self.banks[bank][offset..offset + bytes.len()]);
+    }
+
+    fn write(&mut self, bank: usize, offset: usize, bytes: &[u8]) -> Result<(), StorageError> {
+        let done = self.budget(bytes.len());
+        for (old, new) in self.banks[bank][offset..].iter_mut().zip(&bytes[..done]) {
+            *old &= *new;
+        }
+        if done < bytes.len() {
+            return Err(StorageError::Program);
+        }
+        Ok(())
+    }
+
+    fn erase(&mut self, bank: usize) -> Result<(), StorageError> {
+        let done = self.budget(BANK_SIZE);
+        self.banks[bank][..done].fill(0xff);
+        if done < BANK_SIZE {
+            return Err(StorageError::Erase);
+        }
+        Ok(())
+    }
+}
+
+fn settings(delay_ms: u32) -> Settings {
+    let mut settings = Settings::new();
+    assert!(settings.set(Param::ButtonReleaseDelay, delay_ms));
+    settings
+}
+
+// Delay of the settings loaded after a restart, `None` if defaults apply.
+fn loaded(flash: &mut Flash) -> Option<u32> {
+    flash.power = None;
+    Store::new(flash)
+        .load()
+        .map(|settings| settings.get(Param::ButtonReleaseDelay))
+}
+
+fn save(flash: &mut Flash, delay_ms: u32) -> Result<(), StorageError> {
+    Store::new(flash).save(&settings(delay_ms))
+}
+
+#[test]
+fn saved_settings_are_loaded() {
+    let mut flash = Flash::new();
+    assert_eq!(loaded(&mut flash), None);
+
+    {
+        let mut store = Store::new(&mut flash);
+        store.save(&settings(70)).unwrap();
+        assert_eq!(
+            store
+                .load()
+                .map(|settings| settings.get(Param::ButtonReleaseDelay)),
+            Some(70)
+        );
+        store.save(&settings(80)).unwrap();
+    }
+
+    assert_eq!(loaded(&mut flash), Some(80));
+}
+
+#[test]
+fn log_continues_in_the_other_bank() {
+    let mut flash = Flash::new();
+    for delay_ms in 1..=5 * SLOTS {
+        save(&mut flash, delay_ms).unwrap();
+        assert_eq!(loaded(&mut flash), Some(delay_ms));
+    }
+
+    // latest records fill the bank which was erased last, the previous bank is left intact
+    let erased = |bank: &[u8; BANK_SIZE]| bank.iter().all(|&byte| byte == 0xff);
+    assert!(!erased(&flash.banks[0]));
+    assert!(!erased(&flash.banks[1]));
+}
+
+#[test]
+fn corrupt_record_is_skipped() {
+    let mut flash = Flash::new();
+    save(&mut flash, 70).unwrap();
+    save(&mut flash, 80).unwrap();
+
+    // flips a bit in the payload of the latest record
+    flash.banks[0][512 + 20] ^= 0x04;
+    assert_eq!(loaded(&mut flash), Some(70));
+
+    // corrupt record still takes its slot, the next one follows it
+    save(&mut flash, 90).unwrap();
+    assert_eq!(loaded(&mut flash), Some(90));
+}
+
+#[test]
+fn torn_write_keeps_previous_record() {
+    let mut flash = Flash::new();
+    save(&mut flash, 70).unwrap();
+
+    flash.power = Some(30);
+    assert_eq!(save(&mut flash, 80), Err(StorageError::Program));
+    assert_eq!(loaded(&mut flash), Some(70));
+
+    save(&mut flash, 90).unwrap();
+    assert_eq!(loaded(&mut flash), Some(90));
+}
+
+#[test]
+fn torn_erase_keeps_previous_record() {
+    let mut flash = Flash::new();
+    // fills the first bank, then the second one, so that the first one has to be erased
+    for delay_ms in 1..=2 * SLOTS {
+        save(&mut flash, delay_ms).unwrap();
+    }
+
+    flash.power = Some(BANK_SIZE / 2);
+    assert_eq!(save(&mut flash, 100), Err(StorageError::Erase));
+    assert_eq!(loaded(&mut flash), Some(2 * SLOTS));
+
+    // power fails once the bank has been erased, before the record is complete
+    flash.power = Some(BANK_SIZE + 30);
+    assert_eq!(save(&mut flash, 100), Err(StorageError::Program));
+    assert_eq!(loaded(&mut flash), Some(2 * SLOTS));
+
+    save(&mut flash, 110).unwrap();
+    assert_eq!(loaded(&mut flash), Some(110));
+}
+
+#[test]
+fn room_is_made_ahead_of_saving() {
+    let mut flash = Flash::new();
+    for delay_ms in 1..=SLOTS {
+        save(&mut flash, delay_ms).unwrap();
+    }
+
+    // there is power to erase the other bank once and to program a record
+    flash.power = Some(BANK_SIZE + 512);
+    {
+        let mut store = Store::new(&mut flash);
+        store.make_room().unwrap();
+        store.make_room().unwrap();
+        store.save(&settings(100)).unwrap();
+    }
+    assert_eq!(loaded(&mut flash), Some(100));
+}
+
+#[test]
+fn timing_is_limited() {
+    let mut settings = Settings::new();
+    for param in [
+        Param::PreambleReference,
+        Param::PreambleTolerance,
+        Param::SampleOffset,
+        Param::SampleInterval,
+        Param::SampleTolerance,
+    ] {
+        assert!(settings.set(param, MAX_SAMPLING_US), "{:?}", param);
+        assert!(!settings.set(param, MAX_SAMPLING_US + 1), "{:?}", param);
+        assert!(!settings.set(param, u32::MAX), "{:?}", param);
+        assert_eq!(settings.get(param), MAX_SAMPLING_US);
+    }
+    assert!(!settings.set(Param::SampleInterval, 0));
+
+    for (param, max) in [
+        (Param::ButtonReleaseDelay, MAX_CLICK_DELAY_MS),
+        (Param::DoubleClickDelay, MAX_CLICK_DELAY_MS),
+        (Param::AccelerationTime, MAX_ACCELERATION_TIME_MS),
+    ] {
+        assert!(settings.set(param, max), "{:?}", param);
+        assert!(!settings.set(param, max + 1), "{:?}", param);
+        assert!(!settings.set(param, 4_000_000_000), "{:?}", param);
+        assert_eq!(settings.get(param), max);
+    }
+}
@@ -0,0 +1,119 @@
+use mickey_host_tests::config::TICKS_PER_US;
+use mickey_host_tests::decoder::{self, Decoder, EventKind, Protocol};
+use mickey_host_tests::ir::{Capture, Frame};
+use mickey_host_tests::remote::Sampling;
+use mickey_host_tests::sirc;
+
+// SIRC repeats the frame every 45 ms while a button is held.
+const PERIOD_US: u64 = 45_000;
+
+// Marks and spaces [us] of a 20-bit SIRC frame, starting with the start mark.
+fn frame_20_bit(command: u8, device: u8, extended: u8) -> Vec<u32> {
+    let data =
+        u32::from(command & 0x7f) | u32::from(device & 0x1f) << 7 | u32::from(extended) << 12;
+    let mut durations = vec![2400];
+    for bit in 0..20 {
+        durations.push(600);
+        durations.push(if data & 1 << bit != 0 { 1200 } else { 600 });
+    }
+    durations
+}
+
+// Feeds the frames, sent back to back every `PERIOD_US`, to the capture. Frames are taken
+// the way `frame_task` does: once the capture tells that the frame is complete.
+// Returns the frames with the time [ticks] they started at.
+fn capture(frames: &[Vec<u32>]) -> Vec<(Frame, u64)> {
+    let mut edges = Vec::new();
+    for (index, durations) in frames.iter().enumerate() {
+        let mut time_us = 100_000 + index as u64 * PERIOD_US;
+        let mut mark = true;
+        for &duration in durations {
+            edges.push((time_us * TICKS_PER_US, mark));
+            time_us += u64::from(duration);
+            mark = !mark;
+        }
+        // line goes idle after the last mark
+        edges.push((time_us * TICKS_PER_US, false));
+    }
+    let idle = edges.last().unwrap().0 + 100_000 * TICKS_PER_US;
+
+    let mut capture = Capture::new();
+    let mut captured = Vec::new();
+    let mut started = None;
+    for (ticks, mark) in edges.into_iter().chain([(idle, true)]) {
+        if let Some(start) = started.filter(|_| capture.remaining(ticks).is_none()) {
+            captured.push((*capture.frame(), start));
+            started = None;
+        }
+        if capture.on_edge(ticks, mark) {
+            started = Some(ticks);
+        }
+    }
+    captured
+}
+
+#[test]
+fn longest_frames_fit_between_repetitions() {
+    let frame = frame_20_bit(0x7f, 0x1f, 0xff);
+    assert_eq!(frame.iter().sum::<u32>(), 38_400);
+    assert!(u64::from(frame.iter().sum::<u32>()) < PERIOD_US);
+}
+
+#[test]
+fn back_to_back_20_bit_frames_are_captured_one_by_one() {
+    let frame = frame_20_bit(0x7f, 0x1f, 0xff);
+    let captured = capture(&[frame.clone(), frame.clone(), frame.clone()]);
+
+    assert_eq!(captured.len(), 3);
+    for (captured, _) in &captured {
+        assert_eq!(captured.durations(), &frame[..]);
+        let decoded = sirc::decode_frame(captured).unwrap();
+        assert_eq!(
+            (decoded.command, decoded.device, decoded.extended),
+            (0x7f, 0x1f, 0xff)
+        );
+    }
+}
+
+#[test]
+fn held_button_is_repeated() {
+    let frame = frame_20_bit(0x15, 0x1a, 0x5c);
+    let captured = capture(&[frame.clone(), frame.clone(), frame]);
+
+    let mut decoder = Decoder::new();
+    let sampling = Sampling::new();
+    let events: Vec<_> = captured
+        .iter()
+        .map(|(frame, ticks)| decoder.decode(frame, *ticks, &sampling).unwrap())
+        .collect();
+
+    let kinds: Vec<_> = events.iter().map(|event| event.kind).collect();
+    assert_eq!(
+        kinds,
+        [EventKind::Press, EventKind::Repeat, EventKind::Repeat]
+    );
+    for event in events {
+        assert_eq!(event.code.protocol, Protocol::Sirc);
+        assert_eq!(event.code.address, 0x5c1a);
+        assert_eq!(event.code.command, 0x15);
+    }
+}
+
+#[test]
+fn merged_frames_are_classified_by_start_mark() {
+    // 12-bit frames in a capture which wasn't closed between them, spaces of the bits
+    // are followed by the long space between the frames
+    let frame = frame_20_bit(0x15, 0x01, 0x00);
+    let mut merged = Frame::new();
+    for _ in 0..3 {
+        for &duration in frame.iter().take(25) {
+            merged.push(duration);
+        }
+        merged.push(6_600);
+    }
+
+    let sampling = Sampling::new();
+    assert_eq!(decoder::classify(&merged, &sampling), Some(Protocol::Sirc));
+    let decoded = sirc::decode_frame(&merged).unwrap();
+    assert_eq!((decoded.command, decoded.device), (0x15, 0x01));
+}
@@ -1,10 +1,26 @@
 MEMORY
 {
   /* NOTE K = KiBi = 1024 bytes */
-  FLASH : ORIGIN = 0x08000000, LENGTH = 128K 
+  /* Sectors 2 and 3 (16K each at 0x08008000) are reserved for settings, program memory
+     is split around them: sectors 0 and 1 hold the vector table, read-only data and
+     initial values of RAM, sector 4 holds the code */
+  FLASH : ORIGIN = 0x08000000, LENGTH = 32K
+  SETTINGS : ORIGIN = 0x08008000, LENGTH = 32K
+  CODE : ORIGIN = 0x08010000, LENGTH = 64K
   RAM : ORIGIN = 0x20000000, LENGTH = 32K
 }
 
+/* Code is placed before `.text` of cortex-m-rt, which is left with the reset handler
+   and the hard fault handlers only */
+SECTIONS
+{
+  .code : ALIGN(4)
+  {
+    *(.text .text.*);
+    . = ALIGN(4);
+  } > CODE
+} INSERT BEFORE .text;
+
 /* This is where the call stack will be allocated. */
 /* The stack is of the full descending type. */
 /* NOTE Do NOT modify `_stack_start` unless you know what you are doing */
@@ -0,0 +1,303 @@
+#![no_std]
+
+// Text protocol of the console, shared by the firmware and the host tools.
+// Commands are sent one per line:
+//
+//   help | status
+//   get [<param>]                   - value of the parameter, all of them if omitted
+//   set <param> <value>
+//   keymap list
+//   keymap add <button> <protocol> <address> <command>
+//   keymap remove <button>
+//   keymap clear
+//   mode <mode>                     - mouse, keyboard, absolute or gamepad
+//   enable | disable                - reception of the IR signal
+//   learn                           - start learning session
+//   save | restore | defaults       - persistent settings
+//
+// Each command is answered with lines of output, followed by `ok` or `error: <reason>`.
+// Lines of the log may appear in between.
+//
+// Numbers are decimal, or hexadecimal with the `0x` prefix.
+// Names of buttons, protocols and modes are resolved by the device.
+//
+// Besides the serial console, the same commands are accepted through a vendor-defined HID
+// feature report, for hosts which don't allow CDC devices. Both directions use reports of
+// `CONFIG_REPORT_SIZE` bytes: report ID, length of the payload, payload padded with zeros.
+// SET_REPORT carries a command, GET_REPORT returns the next chunk of its output,
+// or an empty payload if there is nothing more yet.
+
+use core::fmt;
+
+pub const OK: &str = "ok";
+pub const ERROR_PREFIX: &str = "error: ";
+pub const LEARNING_FINISHED: &str = "learning finished";
+pub const LEARNING_ABORTED: &str = "learning aborted";
+
+pub const CONFIG_REPORT_ID: u8 = 4;
+pub const CONFIG_REPORT_SIZE: usize = 64;
+pub const CONFIG_PAYLOAD_SIZE: usize = CONFIG_REPORT_SIZE - 2;
+
+// Builds a configuration report carrying the payload, which is truncated if too long.
+pub fn encode_config_report(payload: &[u8]) -> [u8; CONFIG_REPORT_SIZE] {
+    let len = payload.len().min(CONFIG_PAYLOAD_SIZE);
+    let mut report = [0; CONFIG_REPORT_SIZE];
+    report[0] = CONFIG_REPORT_ID;
+    report[1] = len as u8;
+    report[2..2 + len].copy_from_slice(&payload[..len]);
+    report
+}
+
+// Payload of a configuration report, `None` if the report is malformed.
+pub fn decode_config_report(report: &[u8]) -> Option<&[u8]> {
+    match report {
+        [CONFIG_REPORT_ID, len, payload @ ..] => payload.get(..usize::from(*len)),
+        _ => None,
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Param {
+    PreambleReference,
+    PreambleTolerance,
+    SampleOffset,
+    SampleInterval,
+    SampleTolerance,
+    ButtonReleaseDelay,
+    DoubleClickDelay,
+    Curve,
+    AccelerationTime,
+    MinSpeed,
+    MaxSpeed,
+    MoveStep0,
+    MoveStep1,
+    MoveStep2,
+    MoveStep3,
+    Precision,
+}
+
+impl Param {
+    pub const ALL: [Param; 16] = [
+        Param::PreambleReference,
+        Param::PreambleTolerance,
+        Param::SampleOffset,
+        Param::SampleInterval,
+        Param::SampleTolerance,
+        Param::ButtonReleaseDelay,
+        Param::DoubleClickDelay,
+        Param::Curve,
+        Param::AccelerationTime,
+        Param::MinSpeed,
+        Param::MaxSpeed,
+        Param::MoveStep0,
+        Param::MoveStep1,
+        Param::MoveStep2,
+        Param::MoveStep3,
+        Param::Precision,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Param::PreambleReference => "preamble",
+            Param::PreambleTolerance => "preamble_tolerance",
+            Param::SampleOffset => "sample_offset",
+            Param::SampleInterval => "sample_interval",
+            Param::SampleTolerance => "sample_tolerance",
+            Param::ButtonReleaseDelay => "release_delay",
+            Param::DoubleClickDelay => "double_click_delay",
+            Param::Curve => "curve",
+            Param::AccelerationTime => "acceleration_time",
+            Param::MinSpeed => "min_speed",
+            Param::MaxSpeed => "max_speed",
+            Param::MoveStep0 => "move_step_0",
+            Param::MoveStep1 => "move_step_1",
+            Param::MoveStep2 => "move_step_2",
+            Param::MoveStep3 => "move_step_3",
+            Param::Precision => "precision",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Param::ALL.into_iter().find(|param| param.name() == name)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Command<'a> {
+    Help,
+    Status,
+    Get(Option<Param>),
+    Set(Param, u32),
+    KeymapList,
+    KeymapAdd(KeymapEntry<'a>),
+    KeymapRemove { button: &'a str },
+    KeymapClear,
+    Mode(&'a str),
+    Enable,
+    Disable,
+    Learn,
+    Save,
+    Restore,
+    Defaults,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ParseError {
+    Empty,
+    UnknownCommand,
+    UnknownParam,
+    MissingArgument,
+    TooManyArguments,
+    InvalidNumber,
+}
+
+fn parse_number(text: &str) -> Result<u32, ParseError> {
+    let parsed = match text.strip_prefix("0x") {
+        Some(hex) => u32::from_str_radix(hex, 16),
+        None => text.parse(),
+    };
+    parsed.map_err(|_| ParseError::InvalidNumber)
+}
+
+pub fn parse(line: &str) -> Result<Command<'_>, ParseError> {
+    let mut words = line.split_ascii_whitespace();
+    let mut next = || words.next().ok_or(ParseError::MissingArgument);
+
+    let command = match next().map_err(|_| ParseError::Empty)? {
+        "help" => Command::Help,
+        "status" => Command::Status,
+        "get" => match next().ok() {
+            Some(name) => Command::Get(Some(Param::parse(name).ok_or(ParseError::UnknownParam)?)),
+            None => Command::Get(None),
+        },
+        "set" => {
+            let param = Param::parse(next()?).ok_or(ParseError::UnknownParam)?;
+            Command::Set(param, parse_number(next()?)?)
+        }
+        "keymap" => match next()? {
+            "list" => Command::KeymapList,
+            "add" => Command::KeymapAdd(KeymapEntry {
+                button: next()?,
+                protocol: next()?,
+                address: parse_number(next()?)?,
+                command: parse_number(next()?)?,
+            }),
+            "remove" => Command::KeymapRemove { button: next()? },
+            "clear" => Command::KeymapClear,
+            _ => return Err(ParseError::UnknownCommand),
+        },
+        "mode" => Command::Mode(next()?),
+        "enable" => Command::Enable,
+        "disable" => Command::Disable,
+        "learn" => Command::Learn,
+        "save" => Command::Save,
+        "restore" => Command::Restore,
+        "defaults" => Command::Defaults,
+        _ => return Err(ParseError::UnknownCommand),
+    };
+
+    match next() {
+        Ok(_) => Err(ParseError::TooManyArguments),
+        Err(_) => Ok(command),
+    }
+}
+
+// Formats the command as a line accepted by `parse`.
+impl fmt::Display for Command<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Command::Help => write!(f, "help"),
+            Command::Status => write!(f, "status"),
+            Command::Get(Some(param)) => write!(f, "get {}", param.name()),
+            Command::Get(None) => write!(f, "get"),
+            Command::Set(param, value) => write!(f, "set {} {}", param.name(), value),
+            Command::KeymapList => write!(f, "keymap list"),
+            Command::KeymapAdd(entry) => write!(f, "keymap add {}", entry),
+            Command::KeymapRemove { button } => write!(f, "keymap remove {}", button),
+            Command::KeymapClear => write!(f, "keymap clear"),
+            Command::Mode(mode) => write!(f, "mode {}", mode),
+            Command::Enable => write!(f, "enable"),
+            Command::Disable => write!(f, "disable"),
+            Command::Learn => write!(f, "learn"),
+            Command::Save => write!(f, "save"),
+            Command::Restore => write!(f, "restore"),
+            Command::Defaults => write!(f, "defaults"),
+        }
+    }
+}
+
+// Code bound to a button, as listed by `keymap list`: `<button> <protocol> <address> <command>`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct KeymapEntry<'a> {
+    pub button: &'a str,
+    pub protocol: &'a str,
+    pub address: u32,
+    pub command: u32,
+}
+
+impl<'a> KeymapEntry<'a> {
+    pub fn parse(line: &'a str) -> Option<Self> {
+        let mut words = line.split_ascii_whitespace();
+        let entry = KeymapEntry {
+            button: words.next()?,
+            protocol: words.next()?,
+            address: parse_number(words.next()?).ok()?,
+            command: parse_number(words.next()?).ok()?,
+        };
+        words.next().is_none().then_some(entry)
+    }
+}
+
+impl fmt::Display for KeymapEntry<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {:#x} {:#x}",
+            self.button, self.protocol, self.address, self.command
+        )
+    }
+}
+
+// Value of a parameter, as printed by `get`: `<param> = <value>`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ParamValue {
+    pub param: Param,
+    pub value: u32,
+}
+
+impl ParamValue {
+    pub fn parse(line: &str) -> Option<Self> {
+        let (name, value) = line.split_once(" = ")?;
+        Some(ParamValue {
+            param: Param::parse(name)?,
+            value: parse_number(value).ok()?,
+        })
+    }
+}
+
+impl fmt::Display for ParamValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} = {}", self.param.name(), self.value)
+    }
+}
+
+// Line received from the device.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Reply<'a> {
+    Ok,
+    Error(&'a str),
+    Output(&'a str),
+}
+
+impl<'a> Reply<'a> {
+    pub fn parse(line: &'a str) -> Self {
+        let line = line.trim();
+        if line == OK {
+            Reply::Ok
+        } else if let Some(reason) = line.strip_prefix(ERROR_PREFIX) {
+            Reply::Error(reason)
+        } else {
+            Reply::Output(line)
+        }
+    }
+}
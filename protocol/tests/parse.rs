@@ -0,0 +1,192 @@
+use mickey_protocol::{
+    decode_config_report, encode_config_report, parse, Command, KeymapEntry, Param, ParamValue,
+    ParseError, Reply, CONFIG_PAYLOAD_SIZE, CONFIG_REPORT_ID, CONFIG_REPORT_SIZE,
+};
+
+#[test]
+fn commands_are_parsed() {
+    assert_eq!(parse("help"), Ok(Command::Help));
+    assert_eq!(parse("  status \r"), Ok(Command::Status));
+    assert_eq!(parse("get"), Ok(Command::Get(None)));
+    assert_eq!(
+        parse("get sample_tolerance"),
+        Ok(Command::Get(Some(Param::SampleTolerance)))
+    );
+    assert_eq!(
+        parse("set preamble 17400"),
+        Ok(Command::Set(Param::PreambleReference, 17_400))
+    );
+    assert_eq!(
+        parse("set release_delay 0x40"),
+        Ok(Command::Set(Param::ButtonReleaseDelay, 0x40))
+    );
+    assert_eq!(parse("keymap list"), Ok(Command::KeymapList));
+    assert_eq!(
+        parse("keymap add Ok Nec 0xff00 0x1c"),
+        Ok(Command::KeymapAdd(KeymapEntry {
+            button: "Ok",
+            protocol: "Nec",
+            address: 0xff00,
+            command: 0x1c,
+        }))
+    );
+    assert_eq!(
+        parse("keymap remove Ok"),
+        Ok(Command::KeymapRemove { button: "Ok" })
+    );
+    assert_eq!(parse("keymap clear"), Ok(Command::KeymapClear));
+    assert_eq!(parse("mode gamepad"), Ok(Command::Mode("gamepad")));
+    assert_eq!(parse("enable"), Ok(Command::Enable));
+    assert_eq!(parse("disable"), Ok(Command::Disable));
+    assert_eq!(parse("learn"), Ok(Command::Learn));
+    assert_eq!(parse("save"), Ok(Command::Save));
+    assert_eq!(parse("restore"), Ok(Command::Restore));
+    assert_eq!(parse("defaults"), Ok(Command::Defaults));
+}
+
+#[test]
+fn malformed_commands_are_rejected() {
+    assert_eq!(parse(""), Err(ParseError::Empty));
+    assert_eq!(parse(" \t "), Err(ParseError::Empty));
+    assert_eq!(parse("reboot"), Err(ParseError::UnknownCommand));
+    assert_eq!(parse("keymap show"), Err(ParseError::UnknownCommand));
+    assert_eq!(parse("get speed"), Err(ParseError::UnknownParam));
+    assert_eq!(parse("set speed 1"), Err(ParseError::UnknownParam));
+    assert_eq!(parse("set"), Err(ParseError::MissingArgument));
+    assert_eq!(parse("set sample_offset"), Err(ParseError::MissingArgument));
+    assert_eq!(parse("keymap"), Err(ParseError::MissingArgument));
+    assert_eq!(
+        parse("keymap add Ok Nec 0xff00"),
+        Err(ParseError::MissingArgument)
+    );
+    assert_eq!(parse("mode"), Err(ParseError::MissingArgument));
+    assert_eq!(parse("status now"), Err(ParseError::TooManyArguments));
+    assert_eq!(
+        parse("get double_click_delay 1"),
+        Err(ParseError::TooManyArguments)
+    );
+    assert_eq!(
+        parse("set double_click_delay 1 2"),
+        Err(ParseError::TooManyArguments)
+    );
+}
+
+#[test]
+fn numbers_are_checked() {
+    assert_eq!(
+        parse("set double_click_delay one"),
+        Err(ParseError::InvalidNumber)
+    );
+    assert_eq!(
+        parse("set double_click_delay -1"),
+        Err(ParseError::InvalidNumber)
+    );
+    assert_eq!(
+        parse("set double_click_delay 0x"),
+        Err(ParseError::InvalidNumber)
+    );
+    assert_eq!(
+        parse("set double_click_delay 0xg"),
+        Err(ParseError::InvalidNumber)
+    );
+    assert_eq!(
+        parse("set double_click_delay 4294967296"),
+        Err(ParseError::InvalidNumber)
+    );
+    assert_eq!(
+        parse("set double_click_delay 0xffffffff"),
+        Ok(Command::Set(Param::DoubleClickDelay, u32::MAX))
+    );
+    assert_eq!(
+        parse("keymap add Ok Nec 1 x"),
+        Err(ParseError::InvalidNumber)
+    );
+}
+
+#[test]
+fn every_param_is_named() {
+    for param in Param::ALL {
+        assert_eq!(Param::parse(param.name()), Some(param));
+    }
+}
+
+#[test]
+fn formatted_commands_are_parsed_back() {
+    let entry = KeymapEntry {
+        button: "VolumeUp",
+        protocol: "Rc6",
+        address: 0,
+        command: 0xffff_ffff,
+    };
+    let mut commands = vec![
+        Command::Help,
+        Command::Status,
+        Command::Get(None),
+        Command::KeymapList,
+        Command::KeymapAdd(entry),
+        Command::KeymapRemove { button: "Ok" },
+        Command::KeymapClear,
+        Command::Mode("absolute"),
+        Command::Enable,
+        Command::Disable,
+        Command::Learn,
+        Command::Save,
+        Command::Restore,
+        Command::Defaults,
+    ];
+    for param in Param::ALL {
+        commands.push(Command::Get(Some(param)));
+        commands.push(Command::Set(param, 0));
+        commands.push(Command::Set(param, u32::MAX));
+    }
+
+    for command in commands {
+        let line = command.to_string();
+        assert_eq!(parse(&line), Ok(command), "{}", line);
+    }
+}
+
+#[test]
+fn output_lines_are_parsed_back() {
+    let entry = KeymapEntry {
+        button: "Ok",
+        protocol: "Nec",
+        address: 0xff00,
+        command: 0x1c,
+    };
+    assert_eq!(entry.to_string(), "Ok Nec 0xff00 0x1c");
+    assert_eq!(KeymapEntry::parse(&entry.to_string()), Some(entry));
+    assert_eq!(KeymapEntry::parse("Ok Nec 0xff00"), None);
+    assert_eq!(KeymapEntry::parse("Ok Nec 0xff00 0x1c 0"), None);
+
+    for param in Param::ALL {
+        let value = ParamValue { param, value: 1234 };
+        assert_eq!(ParamValue::parse(&value.to_string()), Some(value));
+    }
+    assert_eq!(ParamValue::parse("precision: 4"), None);
+    assert_eq!(ParamValue::parse("speed = 4"), None);
+
+    assert_eq!(Reply::parse("ok\r"), Reply::Ok);
+    assert_eq!(
+        Reply::parse("error: invalid value"),
+        Reply::Error("invalid value")
+    );
+    assert_eq!(Reply::parse("mode: Mouse"), Reply::Output("mode: Mouse"));
+}
+
+#[test]
+fn config_reports_carry_the_payload() {
+    let report = encode_config_report(b"get precision");
+    assert_eq!(report.len(), CONFIG_REPORT_SIZE);
+    assert_eq!(decode_config_report(&report), Some(&b"get precision"[..]));
+
+    let long = [b'x'; CONFIG_REPORT_SIZE];
+    let report = encode_config_report(&long);
+    assert_eq!(
+        decode_config_report(&report),
+        Some(&long[..CONFIG_PAYLOAD_SIZE])
+    );
+
+    assert_eq!(decode_config_report(&[0, 1, b'x']), None);
+    assert_eq!(decode_config_report(&[CONFIG_REPORT_ID, 2, b'x']), None);
+}
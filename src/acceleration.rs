@@ -0,0 +1,113 @@
+use crate::config::*;
+
+// Moves of the pointer are measured in fractions of a pixel. Fractions that don't make
+// a whole pixel are accumulated, so that slow moves don't get lost.
+pub const SUBPIXELS: u32 = 16;
+// Longest move that fits into a mouse report.
+pub const SPEED_LIMIT: u32 = 127 * SUBPIXELS;
+
+// Whole units of a move in the direction, each of them `unit` long. Fraction of a unit
+// is kept in `remainder` for the next move.
+pub fn accumulate(remainder: &mut i32, direction: i8, speed: u32, unit: u32) -> i8 {
+    let unit = unit.max(1) as i32;
+    let total = *remainder + i32::from(direction) * speed as i32;
+    *remainder = total % unit;
+    (total / unit).clamp(-127, 127) as i8
+}
+
+// Shape of the speed of the pointer over the time the button is held.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, defmt::Format)]
+pub enum Curve {
+    Linear,
+    Quadratic,
+    // steps of `Acceleration::table`, spread evenly over the acceleration time
+    Table,
+}
+
+impl Curve {
+    // all curves in order of their numbers
+    pub const ALL: [Curve; 3] = [Curve::Linear, Curve::Quadratic, Curve::Table];
+}
+
+// Speed of the pointer, i.e. distance moved every `MOTION_INTERVAL_MS` [1/SUBPIXELS px].
+// It grows from `min_speed` to `max_speed` within `time_ms` of holding the button.
+#[derive(Clone, Copy)]
+pub struct Acceleration {
+    pub curve: Curve,
+    pub time_ms: u32,
+    pub min_speed: u32,
+    pub max_speed: u32,
+    // speeds of `Curve::Table`
+    pub table: [u8; 4],
+    // speed is divided by this in the precision mode
+    pub precision: u32,
+}
+
+impl Acceleration {
+    pub const fn new() -> Self {
+        Acceleration {
+            curve: ACCELERATION_CURVE,
+            time_ms: ACCELERATION_TIME_MS,
+            min_speed: MIN_MOVE_SPEED,
+            max_speed: MAX_MOVE_SPEED,
+            table: MOVE_STEPS,
+            precision: PRECISION_DIVISOR,
+        }
+    }
+
+    // Speed after the button has been held for `held_ms`.
+    pub fn speed(&self, held_ms: u32, precise: bool) -> u32 {
+        let speed = match self.curve {
+            Curve::Table => {
+                let len = self.table.len() as u32;
+                let index = scale(len, held_ms.min(self.time_ms), self.time_ms).min(len - 1);
+                u32::from(self.table[index as usize])
+            }
+            _ => self.ramp(held_ms, self.min_speed, self.max_speed),
+        };
+        let speed = speed.min(self.max_speed).min(SPEED_LIMIT);
+        if precise {
+            speed / self.precision
+        } else {
+            speed
+        }
+    }
+
+    // Distance of a single scroll [1/RESOLUTION_MULTIPLIER detent], growing along the same
+    // curve as the speed. `Curve::Table` is scaled to its largest step.
+    pub fn scroll(&self, held_ms: u32) -> u32 {
+        self.ramp(held_ms, SCROLL_MIN_SPEED, SCROLL_MAX_SPEED)
+    }
+
+    // Value between `min` and `max` after the button has been held for `held_ms`.
+    fn ramp(&self, held_ms: u32, min: u32, max: u32) -> u32 {
+        let held_ms = held_ms.min(self.time_ms);
+        let span = max.saturating_sub(min);
+        let linear = scale(span, held_ms, self.time_ms);
+        match self.curve {
+            Curve::Linear => min + linear,
+            Curve::Quadratic => min + scale(linear, held_ms, self.time_ms),
+            Curve::Table => {
+                let len = self.table.len() as u32;
+                let index = scale(len, held_ms, self.time_ms).min(len - 1);
+                let step = u32::from(self.table[index as usize]);
+                let largest = self.table.iter().copied().max().map_or(0, u32::from);
+                min + scale(span, step, largest)
+            }
+        }
+    }
+}
+
+impl Default for Acceleration {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// `value * numerator / denominator`, whole `value` if the denominator is zero.
+fn scale(value: u32, numerator: u32, denominator: u32) -> u32 {
+    if denominator == 0 {
+        return value;
+    }
+    (u64::from(value) * u64::from(numerator) / u64::from(denominator)) as u32
+}
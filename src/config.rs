@@ -1,11 +1,37 @@
+use crate::acceleration::Curve;
+
 pub const MAGIC_PREFIX: u32 = 0x00010295;
-pub const PREAMBLE_REFERENCE: u64 = 435_000;
-pub const PREAMBLE_TOLERANCE: u64 = 10_000;
+pub const TICKS_PER_US: u64 = 25;
+pub const FRAME_GAP_US: u32 = 9_000;
+pub const SIRC_FRAME_GAP_US: u32 = 4_000;
+pub const PREAMBLE_REFERENCE_US: u32 = 17_400;
+pub const PREAMBLE_TOLERANCE_US: u32 = 400;
 pub const MAX_REPETITION_INTERVAL: u64 = 16_000_000;
+pub const NEC_REPETITION_INTERVAL: u64 = 3_750_000;
+pub const RC5_REPETITION_INTERVAL: u64 = 3_750_000;
+pub const SIRC_REPETITION_INTERVAL: u64 = 2_500_000;
 pub const DEBOUNCE_DELAY: u32 = 10_000_000;
+pub const LEARNING_HOLD: u64 = 75_000_000;
+pub const LEARNING_BLINK_MS: u32 = 250;
 pub const BLINK_DURATION_MS: u32 = 100;
-pub const MOUSE_BUTTON_RELEASE_DELAY: u32 = 2_000_000;
-pub const MOUSE_DOUBLE_CLICK_DELAY: u32 = 2_000_000;
-pub const KEYBOARD_BUTTON_RELEASE_DELAY: u32 = 2_000_000;
+pub const MOUSE_BUTTON_RELEASE_DELAY_MS: u32 = 40;
+pub const MOUSE_DOUBLE_CLICK_DELAY_MS: u32 = 40;
+pub const MAX_CLICK_DELAY_MS: u32 = 1_000;
+pub const REPORT_QUEUE_LEN: usize = 16;
 pub const SAMPLE_OFFSET_US: u32 = 8800;
 pub const SAMPLE_INTERVAL_US: u32 = 2150;
+pub const SAMPLE_TOLERANCE_US: u32 = 800;
+pub const MAX_SAMPLING_US: u32 = 100_000;
+pub const ACCELERATION_CURVE: Curve = Curve::Quadratic;
+pub const ACCELERATION_TIME_MS: u32 = 1_500;
+pub const MAX_ACCELERATION_TIME_MS: u32 = 10_000;
+pub const MIN_MOVE_SPEED: u32 = 12;
+pub const MAX_MOVE_SPEED: u32 = 160;
+pub const MOVE_STEPS: [u8; 4] = [12, 30, 80, 160];
+pub const MOTION_INTERVAL_MS: u32 = 8;
+pub const PRECISION_DIVISOR: u32 = 4;
+pub const SCROLL_MIN_SPEED: u32 = 12;
+pub const SCROLL_MAX_SPEED: u32 = 48;
+pub const SETTINGS_SECTORS: [u8; 2] = [2, 3];
+pub const SETTINGS_OFFSETS: [usize; 2] = [0x8000, 0xC000];
+pub const SETTINGS_SIZE: usize = 0x4000;
@@ -0,0 +1,108 @@
+use core::fmt::{self, Write};
+use mickey_protocol::{decode_config_report, encode_config_report};
+use mickey_protocol::{CONFIG_PAYLOAD_SIZE, CONFIG_REPORT_ID};
+use stm32f4xx_hal::otg_fs::UsbBusType;
+use usb_device::class::{ControlIn, ControlOut, UsbClass};
+use usb_device::control::{Recipient, Request, RequestType};
+
+use crate::console::Line;
+
+pub const HID_REQ_GET_REPORT: u8 = 0x01;
+pub const HID_REQ_SET_REPORT: u8 = 0x09;
+const FEATURE_REPORT_TYPE: u16 = 0x03;
+// HID class is created first, so it owns interface 0.
+const HID_INTERFACE: u16 = 0;
+const OUTPUT_SIZE: usize = 1024;
+
+// Configuration channel over the vendor-defined feature report of the HID interface.
+// `HIDClass` rejects GET_REPORT, so this class is polled before it and answers requests
+// for the configuration report on its behalf. Other requests are left to `HIDClass`.
+pub struct ConfigReport {
+    request: Option<Line>,
+    output: [u8; OUTPUT_SIZE],
+    start: usize,
+    end: usize,
+}
+
+impl ConfigReport {
+    pub const fn new() -> Self {
+        ConfigReport {
+            request: None,
+            output: [0; OUTPUT_SIZE],
+            start: 0,
+            end: 0,
+        }
+    }
+
+    // Command received through SET_REPORT, if any.
+    pub fn take_request(&mut self) -> Option<Line> {
+        self.request.take()
+    }
+
+    fn is_config_report(request: &Request) -> bool {
+        is_feature_report(request, CONFIG_REPORT_ID)
+    }
+}
+
+// Whether the class request addresses the feature report of the HID interface with the ID.
+pub fn is_feature_report(request: &Request, id: u8) -> bool {
+    request.request_type == RequestType::Class
+        && request.recipient == Recipient::Interface
+        && request.index == HID_INTERFACE
+        && request.value == FEATURE_REPORT_TYPE << 8 | u16::from(id)
+}
+
+impl Default for ConfigReport {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl UsbClass<UsbBusType> for ConfigReport {
+    fn control_in(&mut self, xfer: ControlIn<UsbBusType>) {
+        let request = xfer.request();
+        if request.request != HID_REQ_GET_REPORT || !Self::is_config_report(request) {
+            return;
+        }
+
+        let len = (self.end - self.start).min(CONFIG_PAYLOAD_SIZE);
+        let report = encode_config_report(&self.output[self.start..self.start + len]);
+        self.start += len;
+        xfer.accept_with(&report).ok();
+    }
+
+    fn control_out(&mut self, xfer: ControlOut<UsbBusType>) {
+        let request = xfer.request();
+        if request.request != HID_REQ_SET_REPORT || !Self::is_config_report(request) {
+            return;
+        }
+
+        match decode_config_report(xfer.data()).and_then(Line::from_bytes) {
+            Some(line) => {
+                // output of the previous command is no longer of interest
+                self.request = Some(line);
+                self.start = 0;
+                self.end = 0;
+                xfer.accept().ok();
+            }
+            None => {
+                xfer.reject().ok();
+            }
+        }
+    }
+}
+
+// Output which doesn't fit into the buffer is dropped.
+impl Write for ConfigReport {
+    fn write_str(&mut self, text: &str) -> fmt::Result {
+        if self.end + text.len() > OUTPUT_SIZE {
+            self.output.copy_within(self.start..self.end, 0);
+            self.end -= self.start;
+            self.start = 0;
+        }
+        let len = text.len().min(OUTPUT_SIZE - self.end);
+        self.output[self.end..self.end + len].copy_from_slice(&text.as_bytes()[..len]);
+        self.end += len;
+        Ok(())
+    }
+}
@@ -0,0 +1,332 @@
+use core::fmt::{self, Debug, Write};
+use usb_device::bus::{UsbBus, UsbBusAllocator};
+use usbd_serial::SerialPort;
+
+use mickey_protocol::{self as protocol, Command, Param, ParamValue, ParseError};
+use mickey_protocol::{ERROR_PREFIX, OK};
+
+use crate::decoder::{IrCode, Protocol};
+use crate::learning::{ButtonMap, Learning};
+use crate::mode::DeviceMode;
+use crate::output::ReportStats;
+use crate::remote::RcButton;
+use crate::settings::{Settings, Store};
+use crate::storage::{Storage, StorageError};
+
+pub const LINE_SIZE: usize = 64;
+const READ_BUFFER_SIZE: usize = 64;
+const WRITE_BUFFER_SIZE: usize = 1024;
+
+pub type Port<B> = SerialPort<'static, B, [u8; READ_BUFFER_SIZE], [u8; WRITE_BUFFER_SIZE]>;
+
+// Line of text typed into the console, without the terminator.
+#[derive(Clone, Copy)]
+pub struct Line {
+    bytes: [u8; LINE_SIZE],
+    len: usize,
+}
+
+impl Line {
+    pub const fn new() -> Self {
+        Line {
+            bytes: [0; LINE_SIZE],
+            len: 0,
+        }
+    }
+
+    // `None` if the text doesn't fit into the line.
+    pub fn from_bytes(text: &[u8]) -> Option<Self> {
+        let mut line = Line::new();
+        line.bytes.get_mut(..text.len())?.copy_from_slice(text);
+        line.len = text.len();
+        Some(line)
+    }
+
+    pub fn as_str(&self) -> &str {
+        core::str::from_utf8(&self.bytes[..self.len]).unwrap_or("")
+    }
+}
+
+impl Default for Line {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Where a command comes from, its output is sent back the same way.
+#[derive(Clone, Copy)]
+pub enum Origin {
+    Console,
+    ConfigReport,
+}
+
+// Line-based text console over the USB serial port (CDC-ACM).
+// Characters are echoed, so that the console can be used from a plain terminal.
+pub struct Console<B: UsbBus + 'static> {
+    port: Port<B>,
+    line: Line,
+    overflow: bool,
+}
+
+impl<B: UsbBus> Console<B> {
+    pub fn new(usb_bus: &'static UsbBusAllocator<B>) -> Self {
+        Console {
+            port: SerialPort::new_with_store(
+                usb_bus,
+                [0; READ_BUFFER_SIZE],
+                [0; WRITE_BUFFER_SIZE],
+            ),
+            line: Line::new(),
+            overflow: false,
+        }
+    }
+
+    pub fn port(&mut self) -> &mut Port<B> {
+        &mut self.port
+    }
+
+    // Collects received characters, returns a line once it is terminated by CR or LF.
+    // Empty lines and lines longer than `LINE_SIZE` are dropped.
+    pub fn read_line(&mut self) -> Option<Line> {
+        let mut byte = [0; 1];
+        while let Ok(1) = self.port.read(&mut byte) {
+            match byte[0] {
+                b'\r' | b'\n' => {
+                    let line = core::mem::take(&mut self.line);
+                    let overflow = core::mem::take(&mut self.overflow);
+                    if line.len > 0 || overflow {
+                        self.write_str("\r\n").ok();
+                    }
+                    if line.len > 0 && !overflow {
+                        return Some(line);
+                    }
+                }
+                // backspace or delete
+                0x08 | 0x7f => {
+                    if self.line.len > 0 {
+                        self.line.len -= 1;
+                        self.write_str("\x08 \x08").ok();
+                    }
+                }
+                byte if self.line.len < LINE_SIZE => {
+                    self.line.bytes[self.line.len] = byte;
+                    self.line.len += 1;
+                    self.port.write(&[byte]).ok();
+                }
+                _ => self.overflow = true,
+            }
+        }
+        None
+    }
+
+    // Prints a line of the log, if the console is open on the host.
+    pub fn log(&mut self, args: fmt::Arguments) {
+        if self.port.dtr() {
+            self.write_fmt(args).ok();
+            self.write_str("\r\n").ok();
+        }
+    }
+}
+
+const HELP: [&str; 13] = [
+    "help                          - this text",
+    "status                        - state of the device",
+    "get [<param>]                 - value of the parameter, all if omitted",
+    "set <param> <value>           - change the parameter",
+    "keymap list                   - learned codes",
+    "keymap add <button> <protocol> <address> <command>",
+    "keymap remove <button>        - forget the learned code",
+    "keymap clear                  - forget all the learned codes",
+    "mode <mode>                   - mouse, keyboard, absolute or gamepad",
+    "enable | disable              - reception of the IR signal",
+    "learn                         - start learning",
+    "save | restore                - write/read settings to/from flash",
+    "defaults                      - default settings, until saved",
+];
+
+// State of the device outside of the settings, reported by `status`.
+#[derive(Clone, Copy)]
+pub struct Status {
+    pub uptime_s: u64,
+    pub reports: ReportStats,
+}
+
+enum Error {
+    Parse(ParseError),
+    Storage(StorageError),
+    Invalid(&'static str),
+}
+
+fn print_line(out: &mut impl Write, args: fmt::Arguments) {
+    out.write_fmt(args).ok();
+    out.write_str("\r\n").ok();
+}
+
+// Parses and executes a command, prints its output followed by the result.
+// Output goes to the console or to the configuration report, wherever the command came from.
+pub fn execute<S: Storage>(
+    out: &mut impl Write,
+    line: &str,
+    status: Status,
+    settings: &mut Settings,
+    mode: &mut DeviceMode,
+    learning: &mut Option<Learning>,
+    store: &mut Store<S>,
+) {
+    let result = match protocol::parse(line) {
+        Ok(command) => run(out, command, status, settings, mode, learning, store),
+        Err(ParseError::Empty) => return,
+        Err(error) => Err(Error::Parse(error)),
+    };
+    match result {
+        Ok(()) => print_line(out, format_args!("{}", OK)),
+        Err(Error::Parse(error)) => {
+            print_line(out, format_args!("{}{:?}, try help", ERROR_PREFIX, error))
+        }
+        Err(Error::Storage(error)) => print_line(out, format_args!("{}{:?}", ERROR_PREFIX, error)),
+        Err(Error::Invalid(reason)) => print_line(out, format_args!("{}{}", ERROR_PREFIX, reason)),
+    }
+}
+
+fn run<S: Storage>(
+    out: &mut impl Write,
+    command: Command,
+    status: Status,
+    settings: &mut Settings,
+    mode: &mut DeviceMode,
+    learning: &mut Option<Learning>,
+    store: &mut Store<S>,
+) -> Result<(), Error> {
+    match command {
+        Command::Help => {
+            for text in HELP {
+                print_line(out, format_args!("{}", text));
+            }
+        }
+        Command::Status => {
+            print_line(out, format_args!("version: {}", env!("CARGO_PKG_VERSION")));
+            print_line(out, format_args!("uptime: {} s", status.uptime_s));
+            print_line(out, format_args!("enabled: {}", settings.enabled));
+            print_line(out, format_args!("mode: {:?}", mode));
+            print_line(
+                out,
+                format_args!(
+                    "reports: sent {}, blocked {}, dropped {}, failed {}",
+                    status.reports.sent,
+                    status.reports.blocked,
+                    status.reports.dropped,
+                    status.reports.failed
+                ),
+            );
+            match learning.as_ref().map(Learning::target) {
+                Some(Some(button)) => print_line(out, format_args!("learning: {:?}", button)),
+                Some(None) => print_line(out, format_args!("learning: finishing")),
+                None => print_line(out, format_args!("learning: no")),
+            }
+        }
+        Command::Get(Some(param)) => {
+            let value = settings.get(param);
+            print_line(out, format_args!("{}", ParamValue { param, value }));
+        }
+        Command::Get(None) => {
+            for param in Param::ALL {
+                let value = settings.get(param);
+                print_line(out, format_args!("{}", ParamValue { param, value }));
+            }
+        }
+        Command::Set(param, value) => {
+            if !settings.set(param, value) {
+                return Err(Error::Invalid("invalid value"));
+            }
+        }
+        Command::KeymapList => {
+            for button in RcButton::ALL {
+                if let Some(code) = settings.button_map.get(button) {
+                    print_line(
+                        out,
+                        format_args!(
+                            "{:?} {:?} {:#x} {:#x}",
+                            button, code.protocol, code.address, code.command
+                        ),
+                    );
+                }
+            }
+        }
+        Command::KeymapAdd(entry) => {
+            let button =
+                find_named(&RcButton::ALL, entry.button).ok_or(Error::Invalid("unknown button"))?;
+            let protocol = find_named(&Protocol::ALL, entry.protocol)
+                .ok_or(Error::Invalid("unknown protocol"))?;
+            let code = IrCode {
+                protocol,
+                address: entry.address,
+                command: entry.command,
+            };
+            settings.button_map.set(button, Some(code));
+        }
+        Command::KeymapRemove { button } => {
+            let button =
+                find_named(&RcButton::ALL, button).ok_or(Error::Invalid("unknown button"))?;
+            settings.button_map.set(button, None);
+        }
+        Command::KeymapClear => settings.button_map = ButtonMap::new(),
+        Command::Mode(name) => {
+            *mode = find_named(&DeviceMode::ALL, name).ok_or(Error::Invalid("unknown mode"))?;
+        }
+        Command::Enable => settings.enabled = true,
+        Command::Disable => settings.enabled = false,
+        Command::Learn => {
+            if learning.is_some() {
+                return Err(Error::Invalid("already learning"));
+            }
+            *learning = Some(Learning::new());
+        }
+        Command::Save => store.save(settings).map_err(Error::Storage)?,
+        Command::Restore => {
+            *settings = store.load().ok_or(Error::Invalid("no saved settings"))?;
+        }
+        Command::Defaults => *settings = Settings::new(),
+    }
+    Ok(())
+}
+
+// Compares `Debug` representation of a value with the name, ignoring case.
+struct NameMatcher<'a> {
+    rest: &'a [u8],
+    matches: bool,
+}
+
+impl Write for NameMatcher<'_> {
+    fn write_str(&mut self, text: &str) -> fmt::Result {
+        match self.rest.split_at_checked(text.len()) {
+            Some((head, tail)) if head.eq_ignore_ascii_case(text.as_bytes()) => self.rest = tail,
+            _ => self.matches = false,
+        }
+        Ok(())
+    }
+}
+
+fn find_named<T: Copy + Debug>(values: &[T], name: &str) -> Option<T> {
+    values.iter().copied().find(|value| {
+        let mut matcher = NameMatcher {
+            rest: name.as_bytes(),
+            matches: true,
+        };
+        write!(matcher, "{:?}", value).ok();
+        matcher.matches && matcher.rest.is_empty()
+    })
+}
+
+// Output which doesn't fit into the buffer is dropped.
+impl<B: UsbBus> Write for Console<B> {
+    fn write_str(&mut self, text: &str) -> fmt::Result {
+        let mut bytes = text.as_bytes();
+        while !bytes.is_empty() {
+            match self.port.write(bytes) {
+                Ok(count) => bytes = &bytes[count..],
+                Err(_) => break,
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,40 @@
+// Usages of the Consumer page (0x0C), sent by `MediaKeyboardReportEx`. Unlike `MediaKey`
+// of usbd-hid, it covers navigation (AC) and launchers (AL) as well, which Android TV
+// and Kodi rely on. Any usage up to 0x514, the maximum of the descriptor, can be added.
+// Those which are not bound by default are kept for custom keymaps.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[repr(u16)]
+pub enum ConsumerUsage {
+    // no control is pressed
+    Unassigned = 0x00,
+    Menu = 0x40,
+    #[allow(dead_code)]
+    BrightnessIncrement = 0x6F,
+    #[allow(dead_code)]
+    BrightnessDecrement = 0x70,
+    #[allow(dead_code)]
+    ChannelIncrement = 0x9C,
+    #[allow(dead_code)]
+    ChannelDecrement = 0x9D,
+    Play = 0xB0,
+    Pause = 0xB1,
+    Record = 0xB2,
+    #[allow(dead_code)]
+    FastForward = 0xB3,
+    #[allow(dead_code)]
+    Rewind = 0xB4,
+    NextTrack = 0xB5,
+    PrevTrack = 0xB6,
+    Stop = 0xB7,
+    #[allow(dead_code)]
+    Eject = 0xB8,
+    Mute = 0xE2,
+    VolumeIncrement = 0xE9,
+    VolumeDecrement = 0xEA,
+    #[allow(dead_code)]
+    AlMediaPlayer = 0x183,
+    #[allow(dead_code)]
+    AlInternetBrowser = 0x196,
+    AcHome = 0x223,
+    AcBack = 0x224,
+}
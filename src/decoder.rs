@@ -0,0 +1,281 @@
+use crate::config::*;
+use crate::ir::Frame;
+use crate::nec::{self, NecFrame};
+use crate::rc5;
+use crate::rc6;
+use crate::remote::{self, RcButton, Sampling};
+use crate::sirc;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, defmt::Format)]
+pub enum Protocol {
+    DvMlg20,
+    Nec,
+    Rc5,
+    Rc6,
+    Sirc,
+}
+
+impl Protocol {
+    pub const ALL: [Protocol; 5] = [
+        Protocol::DvMlg20,
+        Protocol::Nec,
+        Protocol::Rc5,
+        Protocol::Rc6,
+        Protocol::Sirc,
+    ];
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct IrCode {
+    pub protocol: Protocol,
+    pub address: u32,
+    pub command: u32,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, defmt::Format)]
+pub enum EventKind {
+    Press,
+    Repeat,
+    Release,
+}
+
+// `timestamp` is given in ticks of the monotonic timer.
+#[derive(Clone, Copy)]
+pub struct RcEvent {
+    pub code: IrCode,
+    pub kind: EventKind,
+    pub timestamp: u64,
+}
+
+enum Encoding {
+    // marks of constant duration, bits coded by duration of spaces
+    PulseDistance,
+    // spaces of constant duration, bits coded by duration of marks
+    PulseWidth,
+    // marks and spaces of one or two units
+    BiPhase,
+}
+
+fn spread<'a>(durations: impl Iterator<Item = &'a u32>) -> (u32, u32) {
+    durations.fold((u32::MAX, 0), |(min, max), &d| (min.min(d), max.max(d)))
+}
+
+// Determines encoding of the bits following the leader.
+// Returns `None` if it can't be told, e.g. all marks and spaces are equal.
+fn encoding(durations: &[u32]) -> Option<Encoding> {
+    let bits = durations.get(2..)?;
+    if bits.len() < 4 {
+        return None;
+    }
+
+    let marks = spread(bits.iter().step_by(2));
+    let spaces = spread(bits.iter().skip(1).step_by(2));
+
+    let uniform = |(min, max): (u32, u32)| max < min + min / 2;
+    match (uniform(marks), uniform(spaces)) {
+        (true, false) => Some(Encoding::PulseDistance),
+        (false, true) => Some(Encoding::PulseWidth),
+        (false, false) => Some(Encoding::BiPhase),
+        (true, true) => None,
+    }
+}
+
+// Classifies a frame by the timing of its leader and the encoding of its bits.
+pub fn classify(frame: &Frame, sampling: &Sampling) -> Option<Protocol> {
+    let durations = frame.durations();
+    let &leader = durations.first()?;
+
+    if sampling.preamble.matches(leader) {
+        return Some(Protocol::DvMlg20);
+    }
+    if nec::LEADER_MARK.matches(leader) {
+        return Some(Protocol::Nec);
+    }
+    // frames of SIRC merged into one capture have uneven spaces, which look like bi-phase,
+    // so the leader decides unless it's within the window of RC6 as well
+    if sirc::START_MARK.matches(leader) && !rc6::LEADER.matches(leader) {
+        return Some(Protocol::Sirc);
+    }
+
+    match encoding(durations) {
+        Some(Encoding::PulseWidth) | None if sirc::START_MARK.matches(leader) => {
+            Some(Protocol::Sirc)
+        }
+        Some(Encoding::BiPhase) | None if rc6::LEADER.matches(leader) => Some(Protocol::Rc6),
+        // the decoder of RC5 rejects the frame, if it's something else after all
+        _ if rc5::may_be_frame(durations) => Some(Protocol::Rc5),
+        Some(Encoding::BiPhase) | None => Some(Protocol::Rc5),
+        _ => None,
+    }
+}
+
+// Decodes frames of any supported protocol and tells repetitions from new presses.
+pub struct Decoder {
+    last_code: Option<IrCode>,
+    last_ticks: u64,
+    last_toggle: bool,
+}
+
+impl Decoder {
+    pub const fn new() -> Self {
+        Decoder {
+            last_code: None,
+            last_ticks: 0,
+            last_toggle: false,
+        }
+    }
+
+    // Decodes a frame which started at `ticks` of the monotonic timer.
+    pub fn decode(&mut self, frame: &Frame, ticks: u64, sampling: &Sampling) -> Option<RcEvent> {
+        let delta = ticks.wrapping_sub(self.last_ticks);
+        let protocol = classify(frame, sampling)?;
+
+        let (code, repeated) = match protocol {
+            Protocol::DvMlg20 => {
+                let (upper_code, lower_code, _) =
+                    remote::decode_keycode(remote::decode_frame(frame, sampling)?);
+                let code = IrCode {
+                    protocol,
+                    address: upper_code,
+                    command: lower_code,
+                };
+                (
+                    code,
+                    self.last_code == Some(code) && delta < MAX_REPETITION_INTERVAL,
+                )
+            }
+            Protocol::Nec => match nec::decode_frame(frame)? {
+                NecFrame::Code { address, command } => {
+                    let code = IrCode {
+                        protocol,
+                        address: u32::from(address),
+                        command: u32::from(command),
+                    };
+                    (code, false)
+                }
+                // repeat frame carries no code, it refers to the preceding one
+                NecFrame::Repeat => {
+                    let code = self.last_code.filter(|code| {
+                        code.protocol == protocol && delta < MAX_REPETITION_INTERVAL
+                    })?;
+                    (code, true)
+                }
+            },
+            Protocol::Rc5 | Protocol::Rc6 => {
+                let (address, command, toggle) = if protocol == Protocol::Rc5 {
+                    let rc5_frame = rc5::decode_frame(frame)?;
+                    (rc5_frame.address, rc5_frame.command, rc5_frame.toggle)
+                } else {
+                    let rc6_frame = rc6::decode_frame(frame)?;
+                    (rc6_frame.address, rc6_frame.command, rc6_frame.toggle)
+                };
+                let code = IrCode {
+                    protocol,
+                    address: u32::from(address),
+                    command: u32::from(command),
+                };
+                // toggle bit flips on each press, it stays the same while a button is held
+                let repeated = self.last_code == Some(code) && self.last_toggle == toggle;
+                self.last_toggle = toggle;
+                (code, repeated)
+            }
+            Protocol::Sirc => {
+                let sirc_frame = sirc::decode_frame(frame)?;
+                let code = IrCode {
+                    protocol,
+                    address: u32::from(sirc_frame.extended) << 8 | u32::from(sirc_frame.device),
+                    command: u32::from(sirc_frame.command),
+                };
+                // whole frame is repeated every 45 ms while a button is held
+                (
+                    code,
+                    self.last_code == Some(code) && delta < SIRC_REPETITION_INTERVAL,
+                )
+            }
+        };
+
+        self.last_code = Some(code);
+        self.last_ticks = ticks;
+        let kind = if repeated {
+            EventKind::Repeat
+        } else {
+            EventKind::Press
+        };
+        Some(RcEvent {
+            code,
+            kind,
+            timestamp: ticks,
+        })
+    }
+}
+
+impl Default for Decoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Maximum time between frames of a held button [ticks].
+pub fn repetition_interval(protocol: Protocol) -> u64 {
+    match protocol {
+        Protocol::DvMlg20 => MAX_REPETITION_INTERVAL,
+        Protocol::Nec => NEC_REPETITION_INTERVAL,
+        Protocol::Rc5 | Protocol::Rc6 => RC5_REPETITION_INTERVAL,
+        Protocol::Sirc => SIRC_REPETITION_INTERVAL,
+    }
+}
+
+// Keeps track of the button being held, to report its release.
+pub struct HoldTracker {
+    held: Option<RcEvent>,
+}
+
+impl HoldTracker {
+    pub const fn new() -> Self {
+        HoldTracker { held: None }
+    }
+
+    // Returns time [ticks] after which the held button is considered as released.
+    pub fn timeout(&self) -> Option<u64> {
+        self.held
+            .map(|event| repetition_interval(event.code.protocol))
+    }
+
+    // Registers a received event. If it doesn't continue the held button,
+    // release of that button is returned.
+    pub fn track(&mut self, event: &RcEvent) -> Option<RcEvent> {
+        let released = match self.held {
+            Some(held) if event.kind == EventKind::Repeat && held.code == event.code => None,
+            Some(_) => self.release(event.timestamp),
+            None => None,
+        };
+        self.held = Some(*event);
+        released
+    }
+
+    // Releases the held button at `ticks`.
+    pub fn release(&mut self, ticks: u64) -> Option<RcEvent> {
+        self.held.take().map(|held| RcEvent {
+            code: held.code,
+            kind: EventKind::Release,
+            timestamp: ticks,
+        })
+    }
+}
+
+impl Default for HoldTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Translates a code into a button of DV-MLG-20 using keymap of the protocol.
+pub fn lookup(code: &IrCode) -> Option<RcButton> {
+    match code.protocol {
+        Protocol::DvMlg20 => remote::lookup(code.address, code.command),
+        Protocol::Nec => nec::lookup(code.address as u16, code.command as u8),
+        Protocol::Rc5 => rc5::lookup(code.address as u8, code.command as u8),
+        Protocol::Rc6 => rc6::lookup(code.address as u8, code.command as u8),
+        Protocol::Sirc => sirc::lookup(code.address as u16, code.command as u8),
+    }
+}
@@ -1,88 +1,13 @@
-// Descriptor have been generated based on:
-// - usbd_hid::descriptor::MouseReport::desc()
-// - usbd_hid::descriptor::KeyboardReport::desc()
-// - usbd_hid::descriptor::MediaKeyboardReport::desc()
-// Report IDs have been manually added.
+// Descriptor is assembled by the build script from:
+// - reports::MouseReportEx::desc()
+// - reports::KeyboardReportEx::desc()
+// - reports::MediaKeyboardReportEx::desc()
+// - reports::ConfigReportEx::desc()
+// - reports::AbsolutePointerReportEx::desc()
+// - reports::GamepadReportEx::desc()
+// Resolution Multipliers of the mouse report and the hat switch of the gamepad get
+// their extents there as well.
+// Layout of each report is checked against its serializer by the tests of the host,
+// in `host-tests/tests/descriptor.rs`.
 
-pub static HID_DESCRIPTOR: &[u8] = &[
-    0x05, 0x01, // Usage Page (Generic Desktop Ctrls)
-    0x09, 0x02, // Usage (Mouse)
-    0xA1, 0x01, // Collection (Application)
-    0x85, 0x01, //   Report ID (1)
-    0x09, 0x01, //   Usage (Pointer)
-    0xA1, 0x00, //   Collection (Physical)
-    0x05, 0x09, //     Usage Page (Button)
-    0x19, 0x01, //     Usage Minimum (0x01)
-    0x29, 0x08, //     Usage Maximum (0x08)
-    0x15, 0x00, //     Logical Minimum (0)
-    0x25, 0x01, //     Logical Maximum (1)
-    0x75, 0x01, //     Report Size (1)
-    0x95, 0x08, //     Report Count (8)
-    0x81, 0x02, //     Input (Data,Var,Abs,No Wrap,Linear,Preferred State,No Null Position)
-    0x05, 0x01, //     Usage Page (Generic Desktop Ctrls)
-    0x09, 0x30, //     Usage (X)
-    0x17, 0x81, 0xFF, 0xFF, 0xFF, //     Logical Minimum (-128)
-    0x25, 0x7F, //     Logical Maximum (127)
-    0x75, 0x08, //     Report Size (8)
-    0x95, 0x01, //     Report Count (1)
-    0x81, 0x06, //     Input (Data,Var,Rel,No Wrap,Linear,Preferred State,No Null Position)
-    0x09, 0x31, //     Usage (Y)
-    0x81, 0x06, //     Input (Data,Var,Rel,No Wrap,Linear,Preferred State,No Null Position)
-    0x09, 0x38, //     Usage (Wheel)
-    0x81, 0x06, //     Input (Data,Var,Rel,No Wrap,Linear,Preferred State,No Null Position)
-    0x05, 0x0C, //     Usage Page (Consumer)
-    0x0A, 0x38, 0x02, //     Usage (AC Pan)
-    0x81, 0x06, //     Input (Data,Var,Rel,No Wrap,Linear,Preferred State,No Null Position)
-    0xC0, //   End Collection
-    0xC0, // End Collection
-    0x05, 0x01, // Usage Page (Generic Desktop Ctrls)
-    0x09, 0x06, // Usage (Keyboard)
-    0xA1, 0x01, // Collection (Application)
-    0x85, 0x02, //   Report ID (2)
-    0x05, 0x07, //   Usage Page (Kbrd/Keypad)
-    0x19, 0xE0, //   Usage Minimum (0xE0)
-    0x29, 0xE7, //   Usage Maximum (0xE7)
-    0x15, 0x00, //   Logical Minimum (0)
-    0x25, 0x01, //   Logical Maximum (1)
-    0x75, 0x01, //   Report Size (1)
-    0x95, 0x08, //   Report Count (8)
-    0x81, 0x02, //   Input (Data,Var,Abs,No Wrap,Linear,Preferred State,No Null Position)
-    0x19, 0x00, //   Usage Minimum (0x00)
-    0x29, 0xFF, //   Usage Maximum (0xFF)
-    0x26, 0xFF, 0x00, //   Logical Maximum (255)
-    0x75, 0x08, //   Report Size (8)
-    0x95, 0x01, //   Report Count (1)
-    0x81, 0x03, //   Input (Const,Var,Abs,No Wrap,Linear,Preferred State,No Null Position)
-    0x05, 0x08, //   Usage Page (LEDs)
-    0x19, 0x01, //   Usage Minimum (Num Lock)
-    0x29, 0x05, //   Usage Maximum (Kana)
-    0x25, 0x01, //   Logical Maximum (1)
-    0x75, 0x01, //   Report Size (1)
-    0x95, 0x05, //   Report Count (5)
-    0x91,
-    0x02, //   Output (Data,Var,Abs,No Wrap,Linear,Preferred State,No Null Position,Non-volatile)
-    0x95, 0x03, //   Report Count (3)
-    0x91,
-    0x03, //   Output (Const,Var,Abs,No Wrap,Linear,Preferred State,No Null Position,Non-volatile)
-    0x05, 0x07, //   Usage Page (Kbrd/Keypad)
-    0x19, 0x00, //   Usage Minimum (0x00)
-    0x29, 0xDD, //   Usage Maximum (0xDD)
-    0x26, 0xFF, 0x00, //   Logical Maximum (255)
-    0x75, 0x08, //   Report Size (8)
-    0x95, 0x06, //   Report Count (6)
-    0x81, 0x00, //   Input (Data,Array,Abs,No Wrap,Linear,Preferred State,No Null Position)
-    0xC0, // End Collection
-    0x05, 0x0C, // Usage Page (Consumer)
-    0x09, 0x01, // Usage (Consumer Control)
-    0xA1, 0x01, // Collection (Application)
-    0x85, 0x03, //   Report ID (3)
-    0x05, 0x0C, //   Usage Page (Consumer)
-    0x19, 0x00, //   Usage Minimum (Unassigned)
-    0x2A, 0x14, 0x05, //   Usage Maximum (0x0514)
-    0x15, 0x00, //   Logical Minimum (0)
-    0x27, 0xFF, 0xFF, 0x00, 0x00, //   Logical Maximum (65534)
-    0x75, 0x10, //   Report Size (16)
-    0x95, 0x01, //   Report Count (1)
-    0x81, 0x00, //   Input (Data,Array,Abs,No Wrap,Linear,Preferred State,No Null Position)
-    0xC0, // End Collection
-];
+include!(concat!(env!("OUT_DIR"), "/hid_descriptor.rs"));
@@ -0,0 +1,41 @@
+use stm32f4xx_hal::flash::{FlashExt, LockedFlash};
+
+use crate::config::*;
+use crate::storage::{Storage, StorageError};
+
+// Sectors of the internal flash dedicated to the settings, one per bank.
+// They are excluded from the program memory in `memory.x`.
+pub struct FlashStorage {
+    flash: LockedFlash,
+}
+
+impl FlashStorage {
+    pub fn new(flash: LockedFlash) -> Self {
+        FlashStorage { flash }
+    }
+}
+
+impl Storage for FlashStorage {
+    fn capacity(&self) -> usize {
+        SETTINGS_SIZE
+    }
+
+    fn read(&self, bank: usize, offset: usize, bytes: &mut [u8]) {
+        let start = SETTINGS_OFFSETS[bank] + offset;
+        bytes.copy_from_slice(&self.flash.read()[start..start + bytes.len()]);
+    }
+
+    fn write(&mut self, bank: usize, offset: usize, bytes: &[u8]) -> Result<(), StorageError> {
+        self.flash
+            .unlocked()
+            .program(SETTINGS_OFFSETS[bank] + offset, bytes.iter())
+            .map_err(|_| StorageError::Program)
+    }
+
+    fn erase(&mut self, bank: usize) -> Result<(), StorageError> {
+        self.flash
+            .unlocked()
+            .erase(SETTINGS_SECTORS[bank])
+            .map_err(|_| StorageError::Erase)
+    }
+}
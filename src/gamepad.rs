@@ -0,0 +1,48 @@
+use usb_device::{bus::UsbBus, Result};
+use usbd_hid::hid_class::HIDClass;
+
+use crate::reports::{GamepadReportEx, Tagged};
+
+// Buttons in the order of the standard gamepad, as mapped by Linux and Android:
+// buttons 1..15 of the descriptor are BTN_A ... BTN_THUMBR.
+pub const BUTTON_A: u16 = 1 << 0;
+pub const BUTTON_B: u16 = 1 << 1;
+pub const BUTTON_X: u16 = 1 << 3;
+pub const BUTTON_Y: u16 = 1 << 4;
+pub const BUTTON_TL: u16 = 1 << 6;
+pub const BUTTON_TR: u16 = 1 << 7;
+pub const BUTTON_TL2: u16 = 1 << 8;
+pub const BUTTON_TR2: u16 = 1 << 9;
+pub const BUTTON_SELECT: u16 = 1 << 10;
+pub const BUTTON_START: u16 = 1 << 11;
+pub const BUTTON_MODE: u16 = 1 << 12;
+
+// Null state of the hat switch, beyond its logical range.
+pub const HAT_CENTERED: u8 = 8;
+
+// Position of the hat pointing in the direction, clockwise from up.
+pub fn hat(x: i8, y: i8) -> u8 {
+    match (x.signum(), y.signum()) {
+        (0, -1) => 0,
+        (1, -1) => 1,
+        (1, 0) => 2,
+        (1, 1) => 3,
+        (0, 1) => 4,
+        (-1, 1) => 5,
+        (-1, 0) => 6,
+        (-1, -1) => 7,
+        _ => HAT_CENTERED,
+    }
+}
+
+pub fn send_report<B: UsbBus>(hid: &mut HIDClass<'_, B>, buttons: u16, hat: u8) -> Result<usize> {
+    let report = GamepadReportEx {
+        buttons: buttons.to_le_bytes(),
+        hat,
+    };
+    hid.push_input(&Tagged::new(report))
+}
+
+pub fn release<B: UsbBus>(hid: &mut HIDClass<'_, B>) -> Result<usize> {
+    send_report(hid, 0, HAT_CENTERED)
+}
@@ -0,0 +1,177 @@
+use crate::config::*;
+use crate::sirc;
+
+// Capacity of the frame buffer. The longest supported frame (DV-MLG-20) has
+// a preamble followed by at most 52 runs of constant level.
+pub const MAX_DURATIONS: usize = 128;
+
+// Durations of consecutive levels of the demodulated IR signal [us].
+// Entries with even index are marks (carrier present, PB9 low),
+// entries with odd index are spaces (no carrier, PB9 high).
+// Idle state of the line is a space, so each frame starts with a mark.
+#[derive(Clone, Copy)]
+pub struct Frame {
+    durations: [u32; MAX_DURATIONS],
+    len: usize,
+}
+
+impl Frame {
+    pub const fn new() -> Self {
+        Frame {
+            durations: [0; MAX_DURATIONS],
+            len: 0,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    pub fn push(&mut self, duration: u32) {
+        // durations that don't fit are dropped, decoders will reject the frame
+        if self.len < MAX_DURATIONS {
+            self.durations[self.len] = duration;
+            self.len += 1;
+        }
+    }
+
+    pub fn durations(&self) -> &[u32] {
+        &self.durations[..self.len]
+    }
+}
+
+impl Default for Frame {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Expected duration of a mark or a space [us].
+#[derive(Clone, Copy)]
+pub struct Timing {
+    pub reference: u32,
+    pub tolerance: u32,
+}
+
+impl Timing {
+    pub const fn new(reference: u32, tolerance: u32) -> Self {
+        Timing {
+            reference,
+            tolerance,
+        }
+    }
+
+    pub fn matches(&self, duration: u32) -> bool {
+        self.reference.abs_diff(duration) <= self.tolerance
+    }
+}
+
+// Levels of consecutive time slots of a bi-phase (Manchester) frame,
+// where every mark or space lasts an integer number of `unit`s.
+// Bit `n` of `levels` is set if slot `n` is a mark.
+pub struct Slots {
+    levels: u64,
+    len: u32,
+}
+
+impl Slots {
+    pub fn new(frame: &Frame, unit: u32, tolerance: u32) -> Option<Self> {
+        let mut levels: u64 = 0;
+        let mut len: u32 = 0;
+
+        for (index, &duration) in frame.durations().iter().enumerate() {
+            let count = (duration + unit / 2) / unit;
+            if count == 0 || duration.abs_diff(count * unit) > tolerance || len + count > 64 {
+                return None;
+            }
+            if index % 2 == 0 {
+                levels |= (u64::MAX >> (64 - count)) << len;
+            }
+            len += count;
+        }
+
+        Some(Slots { levels, len })
+    }
+
+    pub fn count(&self) -> u32 {
+        self.len
+    }
+
+    // Slots following the frame are spaces, since the line stays idle.
+    pub fn is_mark(&self, index: u32) -> bool {
+        index < self.len && self.levels & (1 << index) != 0
+    }
+}
+
+// Collects edges of the IR signal and splits them into frames.
+// A frame ends when the line stays idle (space) for `FRAME_GAP_US`, which is longer than
+// any space inside a DV-MLG-20 frame. SIRC repeats frames of up to 38.4 ms every 45 ms,
+// leaving gaps as short as 6.6 ms, so a frame led by a SIRC start mark ends after
+// `SIRC_FRAME_GAP_US` instead. Leader of RC6 falls into the same window, its spaces are
+// short enough as well. Marks never end a frame, no matter how long they are.
+pub struct Capture {
+    frame: Frame,
+    last_edge: u64,
+    mark: bool,
+}
+
+impl Capture {
+    pub const fn new() -> Self {
+        Capture {
+            frame: Frame::new(),
+            last_edge: 0,
+            mark: false,
+        }
+    }
+
+    // Records an edge detected at `ticks` of the monotonic timer,
+    // `mark` is the level of the line following the edge.
+    // Returns `true` if the edge opens a new frame.
+    pub fn on_edge(&mut self, ticks: u64, mark: bool) -> bool {
+        let delta = ticks.wrapping_sub(self.last_edge) / TICKS_PER_US;
+        self.last_edge = ticks;
+        self.mark = mark;
+
+        if mark && delta >= u64::from(self.gap_us()) {
+            self.frame.clear();
+            true
+        } else {
+            self.frame.push(delta as u32);
+            false
+        }
+    }
+
+    // Returns remaining time [us] after which the current frame is complete,
+    // or `None` if the frame is already complete at `ticks`.
+    pub fn remaining(&self, ticks: u64) -> Option<u32> {
+        if self.mark {
+            return Some(self.gap_us());
+        }
+
+        let elapsed = ticks.wrapping_sub(self.last_edge) / TICKS_PER_US;
+        let gap = u64::from(self.gap_us());
+        if elapsed < gap {
+            Some((gap - elapsed) as u32)
+        } else {
+            None
+        }
+    }
+
+    pub fn frame(&self) -> &Frame {
+        &self.frame
+    }
+
+    // Idle time [us] which ends the current frame.
+    fn gap_us(&self) -> u32 {
+        match self.frame.durations().first() {
+            Some(&start) if sirc::START_MARK.matches(start) => SIRC_FRAME_GAP_US,
+            _ => FRAME_GAP_US,
+        }
+    }
+}
+
+impl Default for Capture {
+    fn default() -> Self {
+        Self::new()
+    }
+}
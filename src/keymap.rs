@@ -0,0 +1,396 @@
+use usbd_hid::descriptor::KeyboardUsage;
+
+use mickey_grid::Grid;
+
+use crate::acceleration::{accumulate, SUBPIXELS};
+use crate::config::MOTION_INTERVAL_MS;
+use crate::consumer::ConsumerUsage;
+use crate::decoder::EventKind;
+use crate::gamepad::{
+    self, BUTTON_A, BUTTON_B, BUTTON_MODE, BUTTON_SELECT, BUTTON_START, BUTTON_TL, BUTTON_TL2,
+    BUTTON_TR, BUTTON_TR2, BUTTON_X, BUTTON_Y,
+};
+use crate::mode::DeviceMode;
+use crate::motion::Motion;
+use crate::mouse::{BACK_BUTTON, FORWARD_BUTTON, LEFT_BUTTON, MIDDLE_BUTTON, RIGHT_BUTTON};
+use crate::output::{Report, Steps};
+use crate::remote::RcButton;
+use crate::reports::RESOLUTION_MULTIPLIER;
+use crate::settings::Settings;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Action {
+    // Direction of the pointer, which moves as long as the button is held. See `Motion`.
+    Move { x: i8, y: i8 },
+    Scroll { wheel: i8, pan: i8 },
+    // Mouse buttons, clicked the given way.
+    Click(ClickKind, u8),
+    // Keys stay pressed as long as the button of the remote.
+    Key(KeyboardUsage),
+    // Consumer controls: media, volume, navigation of TV and launchers. Held like keys.
+    Consumer(ConsumerUsage),
+    SwitchMode(DeviceMode),
+    // Jumps to the cell of the grid, given by its index in rows, see `Grid`.
+    Jump(u8),
+    // Moves the pointer within the cell of the grid.
+    Refine { x: i8, y: i8 },
+    // Brings the grid back to the whole screen.
+    ResetGrid,
+    // Direction of the hat switch of the gamepad, held as long as the button.
+    Hat { x: i8, y: i8 },
+    // Gamepad buttons, held as long as the button of the remote.
+    GamepadButton(u16),
+    // Slows the pointer down by the precision divisor, or brings it back to full speed.
+    TogglePrecision,
+    // Actions tapped one after another on press of the button, `double_click_delay_ms` apart.
+    Macro(&'static [Action]),
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ClickKind {
+    // Buttons stay pressed as long as the button of the remote.
+    Hold,
+    // Buttons are clicked once, twice or three times on press of the button of the remote.
+    Single,
+    Double,
+    Triple,
+    // Buttons are pressed, or released, until another action changes them.
+    Press,
+    Release,
+    // Buttons are pressed if they were released and the other way around.
+    Toggle,
+}
+
+use Action::*;
+use ClickKind::*;
+use DeviceMode::{Absolute, Gamepad, Keyboard, Mouse};
+
+const DOUBLE_CLICK: [Action; 2] = [Click(Hold, LEFT_BUTTON), Click(Hold, LEFT_BUTTON)];
+
+#[rustfmt::skip]
+pub const KEYMAP: [(DeviceMode, RcButton, Action); 93] = [
+    (Mouse, RcButton::Up, Move { x: 0, y: -1 }),
+    (Mouse, RcButton::Down, Move { x: 0, y: 1 }),
+    (Mouse, RcButton::Left, Move { x: -1, y: 0 }),
+    (Mouse, RcButton::Right, Move { x: 1, y: 0 }),
+    (Mouse, RcButton::Text, Move { x: -1, y: -1 }),
+    (Mouse, RcButton::MyApps, Move { x: 1, y: -1 }),
+    (Mouse, RcButton::Back, Move { x: -1, y: 1 }),
+    (Mouse, RcButton::Exit, Move { x: 1, y: 1 }),
+    (Mouse, RcButton::VolumeUp, Scroll { wheel: 1, pan: 0 }),
+    (Mouse, RcButton::VolumeDown, Scroll { wheel: -1, pan: 0 }),
+    (Mouse, RcButton::PageUp, Scroll { wheel: 0, pan: 1 }),
+    (Mouse, RcButton::PageDown, Scroll { wheel: 0, pan: -1 }),
+    (Mouse, RcButton::Ok, Click(Hold, LEFT_BUTTON)),
+    (Mouse, RcButton::Amazon, Click(Hold, RIGHT_BUTTON)),
+    (Mouse, RcButton::Start, Click(Hold, MIDDLE_BUTTON)),
+    (Mouse, RcButton::Netflix, Click(Double, LEFT_BUTTON)),
+    (Mouse, RcButton::Play, Click(Triple, LEFT_BUTTON)),
+    (Mouse, RcButton::Pause, Click(Single, LEFT_BUTTON)),
+    (Mouse, RcButton::Mute, Click(Toggle, LEFT_BUTTON)),
+    (Mouse, RcButton::Record, Click(Press, LEFT_BUTTON)),
+    (Mouse, RcButton::Stop, Click(Release, LEFT_BUTTON)),
+    (Mouse, RcButton::PrevTrack, Click(Single, BACK_BUTTON)),
+    (Mouse, RcButton::NextTrack, Click(Single, FORWARD_BUTTON)),
+    (Mouse, RcButton::Red, TogglePrecision),
+    (Mouse, RcButton::Green, SwitchMode(Keyboard)),
+    (Keyboard, RcButton::Up, Key(KeyboardUsage::KeyboardUpArrow)),
+    (Keyboard, RcButton::Down, Key(KeyboardUsage::KeyboardDownArrow)),
+    (Keyboard, RcButton::Left, Key(KeyboardUsage::KeyboardLeftArrow)),
+    (Keyboard, RcButton::Right, Key(KeyboardUsage::KeyboardRightArrow)),
+    (Keyboard, RcButton::Ok, Key(KeyboardUsage::KeyboardEnter)),
+    (Keyboard, RcButton::Text, Consumer(ConsumerUsage::AcHome)),
+    (Keyboard, RcButton::MyApps, Consumer(ConsumerUsage::Menu)),
+    (Keyboard, RcButton::Back, Consumer(ConsumerUsage::AcBack)),
+    (Keyboard, RcButton::Exit, Key(KeyboardUsage::KeyboardEscape)),
+    (Keyboard, RcButton::PageUp, Key(KeyboardUsage::KeyboardPageUp)),
+    (Keyboard, RcButton::PageDown, Key(KeyboardUsage::KeyboardPageDown)),
+    (Keyboard, RcButton::VolumeUp, Consumer(ConsumerUsage::VolumeIncrement)),
+    (Keyboard, RcButton::VolumeDown, Consumer(ConsumerUsage::VolumeDecrement)),
+    (Keyboard, RcButton::Mute, Consumer(ConsumerUsage::Mute)),
+    (Keyboard, RcButton::Netflix, Key(KeyboardUsage::KeyboardBackspace)),
+    (Keyboard, RcButton::Start, Key(KeyboardUsage::KeyboardSpacebar)),
+    (Keyboard, RcButton::Amazon, Key(KeyboardUsage::KeyboardDelete)),
+    (Keyboard, RcButton::Record, Consumer(ConsumerUsage::Record)),
+    (Keyboard, RcButton::Stop, Consumer(ConsumerUsage::Stop)),
+    (Keyboard, RcButton::Play, Consumer(ConsumerUsage::Play)),
+    (Keyboard, RcButton::Pause, Consumer(ConsumerUsage::Pause)),
+    (Keyboard, RcButton::NextTrack, Consumer(ConsumerUsage::NextTrack)),
+    (Keyboard, RcButton::PrevTrack, Consumer(ConsumerUsage::PrevTrack)),
+    (Keyboard, RcButton::Red, SwitchMode(Mouse)),
+    (Keyboard, RcButton::Green, SwitchMode(Absolute)),
+    (Absolute, RcButton::Text, Jump(0)),
+    (Absolute, RcButton::Start, Jump(1)),
+    (Absolute, RcButton::MyApps, Jump(2)),
+    (Absolute, RcButton::Netflix, Jump(3)),
+    (Absolute, RcButton::Mute, Jump(4)),
+    (Absolute, RcButton::Amazon, Jump(5)),
+    (Absolute, RcButton::Record, Jump(6)),
+    (Absolute, RcButton::Play, Jump(7)),
+    (Absolute, RcButton::Stop, Jump(8)),
+    (Absolute, RcButton::Up, Refine { x: 0, y: -1 }),
+    (Absolute, RcButton::Down, Refine { x: 0, y: 1 }),
+    (Absolute, RcButton::Left, Refine { x: -1, y: 0 }),
+    (Absolute, RcButton::Right, Refine { x: 1, y: 0 }),
+    (Absolute, RcButton::Exit, ResetGrid),
+    (Absolute, RcButton::Ok, Click(Hold, LEFT_BUTTON)),
+    (Absolute, RcButton::Back, Click(Hold, RIGHT_BUTTON)),
+    (Absolute, RcButton::Pause, Macro(&DOUBLE_CLICK)),
+    (Absolute, RcButton::VolumeUp, Scroll { wheel: 1, pan: 0 }),
+    (Absolute, RcButton::VolumeDown, Scroll { wheel: -1, pan: 0 }),
+    (Absolute, RcButton::Green, SwitchMode(Gamepad)),
+    (Absolute, RcButton::Red, SwitchMode(Keyboard)),
+    (Gamepad, RcButton::Up, Hat { x: 0, y: -1 }),
+    (Gamepad, RcButton::Down, Hat { x: 0, y: 1 }),
+    (Gamepad, RcButton::Left, Hat { x: -1, y: 0 }),
+    (Gamepad, RcButton::Right, Hat { x: 1, y: 0 }),
+    (Gamepad, RcButton::Text, Hat { x: -1, y: -1 }),
+    (Gamepad, RcButton::MyApps, Hat { x: 1, y: -1 }),
+    (Gamepad, RcButton::Netflix, Hat { x: -1, y: 1 }),
+    (Gamepad, RcButton::Amazon, Hat { x: 1, y: 1 }),
+    (Gamepad, RcButton::Ok, GamepadButton(BUTTON_A)),
+    (Gamepad, RcButton::Back, GamepadButton(BUTTON_B)),
+    (Gamepad, RcButton::Red, GamepadButton(BUTTON_X)),
+    (Gamepad, RcButton::Green, GamepadButton(BUTTON_Y)),
+    (Gamepad, RcButton::PrevTrack, GamepadButton(BUTTON_TL)),
+    (Gamepad, RcButton::NextTrack, GamepadButton(BUTTON_TR)),
+    (Gamepad, RcButton::Record, GamepadButton(BUTTON_TL2)),
+    (Gamepad, RcButton::Stop, GamepadButton(BUTTON_TR2)),
+    (Gamepad, RcButton::Play, GamepadButton(BUTTON_START)),
+    (Gamepad, RcButton::Pause, GamepadButton(BUTTON_SELECT)),
+    (Gamepad, RcButton::Start, GamepadButton(BUTTON_MODE)),
+    (Gamepad, RcButton::VolumeUp, Consumer(ConsumerUsage::VolumeIncrement)),
+    (Gamepad, RcButton::VolumeDown, Consumer(ConsumerUsage::VolumeDecrement)),
+    (Gamepad, RcButton::Exit, SwitchMode(Mouse)),
+];
+
+pub fn lookup(mode: DeviceMode, button: RcButton) -> Option<&'static Action> {
+    KEYMAP
+        .iter()
+        .find(|(m, b, _)| *m == mode && *b == button)
+        .map(|(_, _, action)| action)
+}
+
+// State of the actions kept between events.
+pub struct State {
+    // mouse buttons held by `Press` and `Toggle` clicks, they are pressed in every mouse report
+    latched: u8,
+    // fractions of a detent [1/RESOLUTION_MULTIPLIER] scrolled by wheel and pan,
+    // which haven't been reported yet
+    remainder: [i32; 2],
+    precise: bool,
+    // units per detent of wheel and pan, as understood by the host
+    multipliers: [u8; 2],
+    grid: Grid,
+    // mode of the device, buttons go with the absolute pointer in the absolute mode
+    mode: DeviceMode,
+}
+
+const WHEEL: usize = 0;
+const PAN: usize = 1;
+
+impl State {
+    pub const fn new() -> Self {
+        State {
+            latched: 0,
+            remainder: [0, 0],
+            precise: false,
+            multipliers: [1, 1],
+            grid: Grid::new(),
+            mode: Mouse,
+        }
+    }
+
+    pub fn set_multipliers(&mut self, multipliers: [u8; 2]) {
+        self.multipliers = multipliers;
+    }
+
+    pub fn set_mode(&mut self, mode: DeviceMode) {
+        self.mode = mode;
+    }
+
+    // Report of the absolute pointer at the position of the grid.
+    fn absolute(&self, buttons: u8) -> Report {
+        let (x, y) = self.grid.position();
+        Report::Absolute { buttons, x, y }
+    }
+
+    // Report of the mouse buttons. In the absolute mode, they go with the absolute pointer.
+    fn buttons(&self, buttons: u8) -> Report {
+        if self.mode == Absolute {
+            self.absolute(buttons)
+        } else {
+            Report::buttons(buttons)
+        }
+    }
+
+    // Whole units scrolled along the axis. Unit is a whole detent, unless the host
+    // has enabled the multiplier.
+    fn scroll(&mut self, axis: usize, direction: i8, speed: u32) -> i8 {
+        let unit = u32::from(RESOLUTION_MULTIPLIER / self.multipliers[axis].max(1));
+        accumulate(&mut self.remainder[axis], direction, speed, unit)
+    }
+}
+
+impl Default for State {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Schedules reports of the action for the event of its button. Returns the mode to switch to, if any.
+pub fn run(
+    action: &Action,
+    kind: EventKind,
+    held_ms: u32,
+    settings: &Settings,
+    state: &mut State,
+    motion: &mut Motion,
+    steps: &mut Steps,
+) -> Option<DeviceMode> {
+    match (*action, kind) {
+        (Move { x, y }, EventKind::Press | EventKind::Repeat) => {
+            motion.start([x, y], state.latched, state.precise);
+        }
+        (Move { .. }, EventKind::Release) => motion.stop(),
+        (Scroll { wheel, pan }, EventKind::Press | EventKind::Repeat) => {
+            if kind == EventKind::Press {
+                state.remainder = [0, 0];
+            }
+            let speed = settings.acceleration.scroll(held_ms);
+            let wheel = state.scroll(WHEEL, wheel, speed);
+            let pan = state.scroll(PAN, pan, speed);
+            steps.push(Report::Mouse {
+                buttons: state.latched,
+                x: 0,
+                y: 0,
+                wheel,
+                pan,
+            });
+        }
+        // held click of a latched button releases it
+        (Click(Hold, buttons), EventKind::Press) if state.latched & buttons != 0 => {
+            state.latched &= !buttons;
+            steps.push(state.buttons(state.latched));
+        }
+        (Click(Hold, buttons), EventKind::Press) => {
+            steps.push(state.buttons(state.latched | buttons))
+        }
+        (Click(Hold, _), EventKind::Release) => steps.push(state.buttons(state.latched)),
+        (Click(Single, buttons), EventKind::Press) => click(buttons, 1, settings, state, steps),
+        (Click(Double, buttons), EventKind::Press) => click(buttons, 2, settings, state, steps),
+        (Click(Triple, buttons), EventKind::Press) => click(buttons, 3, settings, state, steps),
+        (Click(Press, buttons), EventKind::Press) => {
+            state.latched |= buttons;
+            steps.push(state.buttons(state.latched));
+        }
+        (Click(Release, buttons), EventKind::Press) => {
+            state.latched &= !buttons;
+            steps.push(state.buttons(state.latched));
+        }
+        (Click(Toggle, buttons), EventKind::Press) => {
+            state.latched ^= buttons;
+            steps.push(state.buttons(state.latched));
+        }
+        (Key(key), EventKind::Press) => steps.push(Report::Key(key)),
+        (Key(_), EventKind::Release) => steps.push(Report::ReleaseKeys),
+        (Consumer(usage), EventKind::Press) => steps.push(Report::Consumer(usage)),
+        (Consumer(_), EventKind::Release) => steps.push(Report::ReleaseConsumer),
+        (Jump(index), EventKind::Press) => {
+            if state.grid.jump(usize::from(index)) {
+                steps.push(state.absolute(state.latched));
+            }
+        }
+        (Refine { x, y }, EventKind::Press | EventKind::Repeat) => {
+            state.grid.refine(x, y);
+            steps.push(state.absolute(state.latched));
+        }
+        (ResetGrid, EventKind::Press) => state.grid.reset(),
+        (Hat { x, y }, EventKind::Press) => steps.push(Report::Gamepad {
+            buttons: 0,
+            hat: gamepad::hat(x, y),
+        }),
+        (GamepadButton(buttons), EventKind::Press) => steps.push(Report::Gamepad {
+            buttons,
+            hat: gamepad::HAT_CENTERED,
+        }),
+        (Hat { .. } | GamepadButton(_), EventKind::Release) => steps.push(Report::ReleaseGamepad),
+        (SwitchMode(mode), EventKind::Press) => {
+            // buttons are not left pressed while there is no way to release them
+            if state.latched != 0 {
+                state.latched = 0;
+                steps.push(state.buttons(0));
+            }
+            // absolute mode starts from the whole screen
+            state.grid.reset();
+            return Some(mode);
+        }
+        (TogglePrecision, EventKind::Press) => state.precise = !state.precise,
+        (Macro(actions), EventKind::Press) => {
+            for (index, action) in actions.iter().enumerate() {
+                if index > 0 {
+                    steps.wait(settings.double_click_delay_ms);
+                }
+                tap(action, settings, state, motion, steps);
+            }
+        }
+        _ => {}
+    }
+    None
+}
+
+// Clicks the buttons `count` times. Each click lasts `button_release_delay_ms`,
+// consecutive clicks are `double_click_delay_ms` apart. Latched buttons are released by the click.
+fn click(buttons: u8, count: u8, settings: &Settings, state: &mut State, steps: &mut Steps) {
+    state.latched &= !buttons;
+    for index in 0..count {
+        if index > 0 {
+            steps.wait(settings.double_click_delay_ms);
+        }
+        steps.push(state.buttons(state.latched | buttons));
+        steps.wait(settings.button_release_delay_ms);
+        steps.push(state.buttons(state.latched));
+    }
+}
+
+// Presses and releases the action, as a step of a macro. Like a click, the action is held
+// for `button_release_delay_ms`. The pointer is moved at once by the distance it would cover
+// in that time, since `Motion` only runs until the event has been handled.
+fn tap(
+    action: &Action,
+    settings: &Settings,
+    state: &mut State,
+    motion: &mut Motion,
+    steps: &mut Steps,
+) {
+    if let Move { x, y } = *action {
+        let intervals = (settings.button_release_delay_ms / MOTION_INTERVAL_MS).max(1);
+        let speed = settings.acceleration.speed(0, state.precise);
+        let distance = (speed * intervals / SUBPIXELS).min(i8::MAX as u32) as i8;
+        steps.push(Report::Mouse {
+            buttons: state.latched,
+            x: x.saturating_mul(distance),
+            y: y.saturating_mul(distance),
+            wheel: 0,
+            pan: 0,
+        });
+        return;
+    }
+
+    run(action, EventKind::Press, 0, settings, state, motion, steps);
+    if matches!(
+        action,
+        Click(Hold, _) | Key(_) | Consumer(_) | Hat { .. } | GamepadButton(_)
+    ) {
+        steps.wait(settings.button_release_delay_ms);
+        run(
+            action,
+            EventKind::Release,
+            0,
+            settings,
+            state,
+            motion,
+            steps,
+        );
+    }
+}
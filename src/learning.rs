@@ -0,0 +1,99 @@
+use crate::config::LEARNING_HOLD;
+use crate::decoder::{self, IrCode};
+use crate::mode::DeviceMode;
+use crate::remote::RcButton;
+
+// Codes learned for the buttons, indexed by the button.
+// Learned codes take precedence over keymaps of the protocols.
+#[derive(Clone, Copy)]
+pub struct ButtonMap {
+    codes: [Option<IrCode>; RcButton::ALL.len()],
+}
+
+impl ButtonMap {
+    pub const fn new() -> Self {
+        ButtonMap {
+            codes: [None; RcButton::ALL.len()],
+        }
+    }
+
+    pub fn get(&self, button: RcButton) -> Option<IrCode> {
+        self.codes[button as usize]
+    }
+
+    pub fn set(&mut self, button: RcButton, code: Option<IrCode>) {
+        self.codes[button as usize] = code;
+    }
+
+    pub fn lookup(&self, code: &IrCode) -> Option<RcButton> {
+        RcButton::ALL
+            .iter()
+            .find(|button| self.codes[**button as usize] == Some(*code))
+            .copied()
+            .or_else(|| decoder::lookup(code))
+    }
+}
+
+impl Default for ButtonMap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Walks through all the buttons, binding each of them to the code received next.
+pub struct Learning {
+    index: usize,
+    map: ButtonMap,
+}
+
+impl Learning {
+    pub const fn new() -> Self {
+        Learning {
+            index: 0,
+            map: ButtonMap::new(),
+        }
+    }
+
+    // Button waiting for a code, `None` once all the buttons are learned.
+    pub fn target(&self) -> Option<RcButton> {
+        RcButton::ALL.get(self.index).copied()
+    }
+
+    // Binds the code to the current target. Codes already bound
+    // to another button in this session are ignored.
+    pub fn learn(&mut self, code: &IrCode) -> bool {
+        let Some(target) = self.target() else {
+            return false;
+        };
+        if RcButton::ALL[..self.index]
+            .iter()
+            .any(|button| self.map.get(*button) == Some(*code))
+        {
+            return false;
+        }
+        self.map.set(target, Some(*code));
+        self.index += 1;
+        true
+    }
+
+    // Leaves the current target unbound, so that keymap of the protocol applies.
+    pub fn skip(&mut self) {
+        self.index += 1;
+    }
+
+    pub fn map(&self) -> &ButtonMap {
+        &self.map
+    }
+}
+
+impl Default for Learning {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Tells if the button of the remote, held for `held` ticks, starts learning. Games hold
+// buttons for long, so holding doesn't start learning in the gamepad mode.
+pub fn is_learning_hold(mode: DeviceMode, button: RcButton, held: u64) -> bool {
+    mode != DeviceMode::Gamepad && button == RcButton::Stop && held >= LEARNING_HOLD
+}
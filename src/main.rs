@@ -4,20 +4,41 @@
 
 use rtic_mickey_mouse as _;
 
+mod acceleration;
 mod config;
+mod config_report;
+mod console;
+mod consumer;
+mod decoder;
 mod descriptor;
+mod flash;
+mod gamepad;
+mod ir;
 mod keyboard;
+mod keymap;
+mod learning;
 mod mode;
+mod motion;
 mod mouse;
+mod multiplier;
+mod nec;
+mod output;
+mod rc5;
+mod rc6;
 mod remote;
+mod reports;
+mod settings;
+mod sirc;
+mod storage;
 
 #[rtic::app(device = stm32f4xx_hal::pac, dispatchers = [SPI1])]
 mod app {
 
     use core::mem::MaybeUninit;
-    use fugit::ExtU32;
+    use mickey_protocol::{self as protocol, Command, LEARNING_ABORTED, LEARNING_FINISHED};
     use rtic_monotonics::{rtic_time::embedded_hal_async::delay::DelayNs, stm32::prelude::*};
     use rtic_sync::{channel::*, make_channel};
+    use stm32f4xx_hal::flash::LockedFlash;
     use stm32f4xx_hal::gpio::{gpioa::PA0, gpioa::PA1, gpiob::PB9, gpioc::PC13};
     use stm32f4xx_hal::gpio::{Edge, ExtiPin, Input, Output, PushPull};
     use stm32f4xx_hal::otg_fs::{UsbBus, UsbBusType, USB};
@@ -26,29 +47,46 @@ mod app {
     use usbd_hid::hid_class::HIDClass;
 
     use crate::config::*;
+    use crate::config_report::ConfigReport;
+    use crate::console::{self, Console, Line, Origin, Status};
+    use crate::decoder::{Decoder, EventKind, HoldTracker, RcEvent};
     use crate::descriptor::HID_DESCRIPTOR;
-    use crate::keyboard;
+    use crate::flash::FlashStorage;
+    use crate::ir::Capture;
+    use crate::keymap;
+    use crate::learning::{is_learning_hold, Learning};
     use crate::mode::DeviceMode;
-    use crate::mouse;
-    use crate::remote;
-    use crate::remote::{decode_keycode, RcButton};
+    use crate::motion::Motion;
+    use crate::multiplier::ResolutionMultiplier;
+    use crate::output::{ReportQueue, Step, Steps, MAX_STEPS};
+    use crate::settings::{Settings, Store};
 
     stm32_tim2_monotonic!(Mono, 25_000_000); // tick rate [Hz]
 
     #[shared]
     struct Shared {
         hid: HIDClass<'static, UsbBusType>,
+        reports: ReportQueue,
         btn: PA0<Input>,
         ir: PB9<Input>,
         led: PC13<Output<PushPull>>,
-        enabled: bool,
+        capture: Capture,
+        console: Console<UsbBusType>,
+        config_report: ConfigReport,
+        multiplier: ResolutionMultiplier,
+        motion: Motion,
+        settings: Settings,
+        mode: DeviceMode,
+        store: Store<FlashStorage>,
+        learning: Option<Learning>,
     }
 
     #[local]
     struct Local {
         usb_dev: UsbDevice<'static, UsbBus<USB>>,
         sample_clk: PA1<Output<PushPull>>,
-        keycode_tx: Sender<'static, u64, 10>,
+        event_tx: Sender<'static, RcEvent, 10>,
+        line_tx: Sender<'static, (Origin, Line), 4>,
     }
 
     #[init(local = [ep_memory: [u32; 1024] = [0; 1024], usb_bus: MaybeUninit<UsbBusAllocator<UsbBusType>> = MaybeUninit::uninit()])]
@@ -74,8 +112,16 @@ mod app {
         ir.enable_interrupt(&mut ctx.device.EXTI);
         ir.trigger_on_edge(&mut ctx.device.EXTI, Edge::RisingFalling);
 
+        // settings saved in flash, defaults if there are none
+        let store = Store::new(FlashStorage::new(LockedFlash::new(ctx.device.FLASH)));
+        let settings = store.load().unwrap_or_default();
+
         let mut led = gpioc.pc13.into_push_pull_output();
-        led.set_low();
+        if settings.enabled {
+            led.set_low();
+        } else {
+            led.set_high();
+        }
 
         let mut sample_clk = gpioa.pa1.into_push_pull_output();
         sample_clk.set_high();
@@ -93,6 +139,7 @@ mod app {
         let usb_bus = usb_bus.write(UsbBus::new(usb, ctx.local.ep_memory));
 
         let hid = HIDClass::new(usb_bus, HID_DESCRIPTOR, 60);
+        let console = Console::new(usb_bus);
 
         let usb_dev = UsbDeviceBuilder::new(usb_bus, UsbVidPid(0x05df, 0x16c0))
             .strings(&[StringDescriptors::default()
@@ -100,176 +147,497 @@ mod app {
                 .product("MicKeyMouse")
                 .serial_number("0001")])
             .unwrap()
-            .device_class(0)
+            .composite_with_iads()
             .build();
 
-        let (keycode_tx, keycode_rx) = make_channel!(u64, 10);
-        let enabled = true;
+        let (event_tx, event_rx) = make_channel!(RcEvent, 10);
+        let capture = Capture::new();
+        let learning = None;
+
+        let (line_tx, line_rx) = make_channel!((Origin, Line), 4);
+
+        let (step_tx, step_rx) = make_channel!(Step, MAX_STEPS);
+        let (motion_tx, motion_rx) = make_channel!((), 1);
 
-        receiver_task::spawn(keycode_rx).unwrap();
+        receiver_task::spawn(event_rx, step_tx, motion_tx).unwrap();
+        output_task::spawn(step_rx).unwrap();
+        motion_task::spawn(motion_rx).unwrap();
+        console_task::spawn(line_rx).unwrap();
 
         (
             Shared {
                 hid,
+                reports: ReportQueue::new(),
                 btn,
                 ir,
                 led,
-                enabled,
+                capture,
+                console,
+                config_report: ConfigReport::new(),
+                multiplier: ResolutionMultiplier::new(),
+                motion: Motion::new(),
+                settings,
+                mode: DeviceMode::Mouse,
+                store,
+                learning,
             },
             Local {
                 usb_dev,
                 sample_clk,
-                keycode_tx,
+                event_tx,
+                line_tx,
             },
         )
     }
 
-    #[task(shared = [hid])]
+    #[task(shared = [console, settings, mode, learning, multiplier, motion])]
     async fn receiver_task(
         ctx: receiver_task::Context,
-        mut keycode_rx: Receiver<'static, u64, 10>,
+        mut event_rx: Receiver<'static, RcEvent, 10>,
+        mut step_tx: Sender<'static, Step, MAX_STEPS>,
+        mut motion_tx: Sender<'static, (), 1>,
     ) {
-        let mut hid = ctx.shared.hid;
-        const MAX_SPEED: u8 = 3;
-        let mut speed: u8 = 0;
-        let mut device_mode: DeviceMode = DeviceMode::Mouse;
+        let mut console = ctx.shared.console;
+        let mut settings = ctx.shared.settings;
+        let mut mode = ctx.shared.mode;
+        let mut learning = ctx.shared.learning;
+        let mut multiplier = ctx.shared.multiplier;
+        let mut motion = ctx.shared.motion;
+        let mut pressed_at: u64 = 0;
+        let mut state = keymap::State::new();
+
+        let mut tracker = HoldTracker::new();
 
         loop {
-            while let Ok(keycode) = keycode_rx.recv().await {
-                let (upper_code, lower_code, flag_repeated) = decode_keycode(keycode);
-                if upper_code != MAGIC_PREFIX {
-                    continue;
+            // held button is released if it isn't repeated in time
+            let received = match tracker.timeout() {
+                Some(timeout) => {
+                    let timeout = <Mono as Monotonic>::Duration::from_ticks(timeout);
+                    Mono::timeout_after(timeout, event_rx.recv()).await
                 }
-
-                if flag_repeated {
-                    if speed < MAX_SPEED {
-                        speed += 1;
-                    }
-                } else {
-                    speed = 0;
+                None => Ok(event_rx.recv().await),
+            };
+            let events = match received {
+                Ok(Ok(event)) => [tracker.track(&event), Some(event)],
+                Ok(Err(_)) => continue,
+                Err(_) => [tracker.release(Mono::now().ticks()), None],
+            };
+
+            for event in events.into_iter().flatten() {
+                // speed of the pointer follows the time the button has been held
+                if event.kind == EventKind::Press {
+                    pressed_at = event.timestamp;
                 }
+                let held_ms =
+                    (event.timestamp.wrapping_sub(pressed_at) / (TICKS_PER_US * 1_000)) as u32;
 
                 defmt::println!(
-                    "lower_code={:#010x}, repeated={}, speed={}",
-                    lower_code,
-                    flag_repeated,
-                    speed
+                    "protocol={}, address={:#x}, command={:#x}, kind={}, held={}ms",
+                    event.code.protocol,
+                    event.code.address,
+                    event.code.command,
+                    event.kind,
+                    held_ms
                 );
-
-                let maybe_button = RcButton::try_from(lower_code);
-                match maybe_button {
-                    Ok(button) => {
-                        hid.lock(|hid| {
-                            device_mode = match device_mode {
-                                DeviceMode::Mouse => mouse::handle_mouse_event(hid, button, speed),
-                                DeviceMode::Keyboard => {
-                                    keyboard::handle_keyboard_event(hid, button, speed)
-                                }
-                            };
-                        });
+                console.lock(|console| {
+                    console.log(format_args!(
+                        "protocol={:?}, address={:#x}, command={:#x}, kind={:?}, held={}ms",
+                        event.code.protocol,
+                        event.code.address,
+                        event.code.command,
+                        event.kind,
+                        held_ms
+                    ))
+                });
+
+                // while learning, codes are bound to buttons instead of driving HID,
+                // releases still pass through to let go of keys held beforehand
+                if event.kind != EventKind::Release {
+                    let learned = learning.lock(|learning| {
+                        let session = learning.as_mut()?;
+                        let target = session.target();
+                        let learned = event.kind == EventKind::Press && session.learn(&event.code);
+                        Some(target.filter(|_| learned))
+                    });
+                    match learned {
+                        Some(Some(target)) => {
+                            defmt::println!("learned {}", target);
+                            console
+                                .lock(|console| console.log(format_args!("learned {:?}", target)));
+                            continue;
+                        }
+                        Some(None) => continue,
+                        None => {}
                     }
-                    Err(_) => {}
+                }
+
+                let current = settings.lock(|settings| *settings);
+                let maybe_button = current.button_map.lookup(&event.code);
+
+                let device_mode = mode.lock(|mode| *mode);
+
+                // holding Stop long enough starts learning
+                let held = event.timestamp.wrapping_sub(pressed_at);
+                if event.kind == EventKind::Repeat
+                    && maybe_button
+                        .is_some_and(|button| is_learning_hold(device_mode, button, held))
+                {
+                    learning.lock(|learning| *learning = Some(Learning::new()));
+                    learning_task::spawn().ok();
+                    continue;
+                }
+
+                let Some(action) =
+                    maybe_button.and_then(|button| keymap::lookup(device_mode, button))
+                else {
+                    continue;
+                };
+                let mut steps = Steps::new();
+                state.set_multipliers(multiplier.lock(|multiplier| multiplier.multipliers()));
+                state.set_mode(device_mode);
+                let (new_mode, moving) = motion.lock(|motion| {
+                    let new_mode = keymap::run(
+                        action, event.kind, held_ms, &current, &mut state, motion, &mut steps,
+                    );
+                    (new_mode, motion.is_active())
+                });
+                if let Some(new_mode) = new_mode {
+                    mode.lock(|mode| *mode = new_mode);
+                }
+                // wakes up motion_task, unless it's already woken up
+                if moving {
+                    motion_tx.try_send(()).ok();
+                }
+                for step in steps.as_slice() {
+                    step_tx.send(*step).await.ok();
                 }
             }
         }
     }
 
-    #[task(priority=1, local = [keycode_tx, sample_clk, last_ticks : u64 = 0, last_keycode : u64 = 0], shared = [ir, led])]
-    async fn sample_clk_task(ctx: sample_clk_task::Context) {
-        let timestamp = Mono::now();
-        let sample_clk = ctx.local.sample_clk;
-        let last_keycode = ctx.local.last_keycode;
-        let last_ticks = ctx.local.last_ticks;
-        let keycode_tx = ctx.local.keycode_tx;
-        let mut led = ctx.shared.led;
-        let mut ir = ctx.shared.ir;
-        let mut keycode: u64 = 0;
-
-        let interval = SAMPLE_INTERVAL_US.micros();
-        let offset = SAMPLE_OFFSET_US.micros();
+    // Queues scheduled reports for the host, waiting between them as requested.
+    // Resources are locked only to queue a report, so USB is polled in the meantime.
+    #[task(priority=1, shared = [hid, reports])]
+    async fn output_task(
+        ctx: output_task::Context,
+        mut step_rx: Receiver<'static, Step, MAX_STEPS>,
+    ) {
+        let mut hid = ctx.shared.hid;
+        let mut reports = ctx.shared.reports;
 
-        for sample_cnt in 0u32..(remote::DATA_WIDTH) {
-            Mono::delay_until(timestamp + interval * sample_cnt + offset).await;
-            sample_clk.toggle();
-            keycode <<= 1;
-            if ir.lock(|pin| pin.is_high()) {
-                keycode |= 1;
+        while let Ok(step) = step_rx.recv().await {
+            if step.delay_ms > 0 {
+                Mono::delay(u64::from(step.delay_ms).millis()).await;
             }
+            (&mut hid, &mut reports).lock(|hid, reports| {
+                reports.push(step.report);
+                reports.flush(hid);
+            });
         }
+    }
 
-        let ticks = timestamp.ticks();
-        let delta = ticks.wrapping_sub(*last_ticks);
-        let mut flags: u8 = 0;
-        //defmt::println!("delta= {}", delta);
-        if *last_keycode == keycode {
-            if delta < MAX_REPETITION_INTERVAL {
-                flags |= 0x1;
+    // Moves the pointer every `MOTION_INTERVAL_MS` while `Motion` is active,
+    // so that it glides between repetitions of the remote control.
+    #[task(priority=1, shared = [hid, reports, motion, settings])]
+    async fn motion_task(ctx: motion_task::Context, mut motion_rx: Receiver<'static, (), 1>) {
+        let mut hid = ctx.shared.hid;
+        let mut reports = ctx.shared.reports;
+        let mut motion = ctx.shared.motion;
+        let mut settings = ctx.shared.settings;
+
+        while let Ok(()) = motion_rx.recv().await {
+            loop {
+                let acceleration = settings.lock(|settings| settings.acceleration);
+                let Some(moved) = motion.lock(|motion| motion.step(&acceleration)) else {
+                    break;
+                };
+                if let Some(report) = moved {
+                    (&mut hid, &mut reports).lock(|hid, reports| {
+                        reports.push(report);
+                        reports.flush(hid);
+                    });
+                }
+                Mono::delay(u64::from(MOTION_INTERVAL_MS).millis()).await;
             }
         }
-        *last_ticks = ticks;
-        *last_keycode = keycode;
-
-        keycode = (u64::from(flags) << remote::FLAGS_OFFSET) | keycode;
+    }
 
+    #[task(priority=1, local = [event_tx, sample_clk, decoder: Decoder = Decoder::new()], shared = [capture, settings])]
+    async fn frame_task(ctx: frame_task::Context) {
+        let timestamp = Mono::now();
+        let sample_clk = ctx.local.sample_clk;
+        let decoder = ctx.local.decoder;
+        let event_tx = ctx.local.event_tx;
+        let mut capture = ctx.shared.capture;
+        let mut settings = ctx.shared.settings;
+
+        // wait until the line stays idle long enough to close the frame
+        sample_clk.set_low();
+        let frame = loop {
+            let ticks = Mono::now().ticks();
+            match capture.lock(|capture| match capture.remaining(ticks) {
+                Some(remaining) => Err(remaining),
+                None => Ok(*capture.frame()),
+            }) {
+                Ok(frame) => break frame,
+                Err(remaining) => Mono::delay(u64::from(remaining).micros()).await,
+            }
+        };
         sample_clk.set_high();
-        //defmt::println!("keycode={:#018x}", keycode);
-        let _ = keycode_tx.send(keycode).await;
+
+        let sampling = settings.lock(|settings| settings.sampling);
+        let Some(event) = decoder.decode(&frame, timestamp.ticks(), &sampling) else {
+            return;
+        };
+
+        let _ = event_tx.send(event).await;
+        blink_task::spawn().ok();
+    }
+
+    #[task(priority=1, shared = [led])]
+    async fn blink_task(ctx: blink_task::Context) {
+        let mut led = ctx.shared.led;
         led.lock(|pin| pin.set_high());
         DelayNs::delay_ms(&mut Mono, BLINK_DURATION_MS).await;
         led.lock(|pin| pin.set_low());
     }
 
-    #[task(binds = EXTI9_5, local = [last_ticks : u64 = 0], shared = [ir, enabled])]
-    fn on_ir(mut ctx: on_ir::Context) {
-        ctx.shared.ir.lock(ExtiPin::clear_interrupt_pending_bit);
-        let last_ticks = ctx.local.last_ticks;
-        let mut enabled = ctx.shared.enabled;
+    #[task(binds = EXTI9_5, priority = 2, shared = [ir, settings, capture])]
+    fn on_ir(ctx: on_ir::Context) {
+        let mut ir = ctx.shared.ir;
+        let mut settings = ctx.shared.settings;
+        let mut capture = ctx.shared.capture;
 
-        let timestamp = Mono::now();
-        let ticks = timestamp.ticks();
-        let delta = ticks.wrapping_sub(*last_ticks);
-        *last_ticks = ticks;
+        ir.lock(ExtiPin::clear_interrupt_pending_bit);
+        let ticks = Mono::now().ticks();
+        let mark = ir.lock(|pin| pin.is_low());
 
-        if PREAMBLE_REFERENCE - PREAMBLE_TOLERANCE <= delta
-            && delta < PREAMBLE_REFERENCE + PREAMBLE_TOLERANCE
+        if capture.lock(|capture| capture.on_edge(ticks, mark))
+            && settings.lock(|settings| settings.enabled)
         {
-            enabled.lock(|enabled| {
-                if *enabled {
-                    sample_clk_task::spawn().ok();
-                }
-            });
+            frame_task::spawn().ok();
         }
     }
 
-    #[task(binds=OTG_FS, local = [usb_dev], shared = [hid])]
+    #[task(binds=OTG_FS, priority = 2, local = [usb_dev, line_tx], shared = [hid, reports, console, config_report, multiplier])]
     fn on_usb(ctx: on_usb::Context) {
         let usb_dev = ctx.local.usb_dev;
-        let mut hid = ctx.shared.hid;
-        hid.lock(|hid| if !usb_dev.poll(&mut [hid]) {});
+        let line_tx = ctx.local.line_tx;
+        let hid = ctx.shared.hid;
+        let reports = ctx.shared.reports;
+        let console = ctx.shared.console;
+        let config_report = ctx.shared.config_report;
+        let multiplier = ctx.shared.multiplier;
+
+        (hid, reports, console, config_report, multiplier).lock(
+            |hid, reports, console, config_report, multiplier| {
+                // feature reports go first to answer their requests instead of HID class
+                if usb_dev.poll(&mut [config_report, multiplier, hid, console.port()]) {
+                    while let Some(line) = console.read_line() {
+                        line_tx.try_send((Origin::Console, line)).ok();
+                    }
+                    if let Some(line) = config_report.take_request() {
+                        line_tx.try_send((Origin::ConfigReport, line)).ok();
+                    }
+                }
+                // queued reports go out as soon as the IN endpoint is free again
+                reports.flush(hid);
+            },
+        );
     }
 
-    #[task(binds = EXTI0, shared = [btn, led, enabled])]
-    fn on_btn(ctx: on_btn::Context) {
-        let mut btn = ctx.shared.btn;
+    #[task(priority=1, shared = [console, config_report, reports, settings, mode, store, learning, led])]
+    async fn console_task(
+        ctx: console_task::Context,
+        mut line_rx: Receiver<'static, (Origin, Line), 4>,
+    ) {
+        let mut console = ctx.shared.console;
+        let mut config_report = ctx.shared.config_report;
+        let mut reports = ctx.shared.reports;
+        let mut settings = ctx.shared.settings;
+        let mut mode = ctx.shared.mode;
+        let mut store = ctx.shared.store;
+        let mut learning = ctx.shared.learning;
         let mut led = ctx.shared.led;
-        let mut enabled = ctx.shared.enabled;
 
-        btn.lock(ExtiPin::clear_interrupt_pending_bit);
-        enabled.lock(|enabled| {
-            btn.lock(|btn| {
-                if btn.is_low() {
-                    if *enabled {
-                        defmt::println!("disabled");
-                        *enabled = false;
-                        led.lock(|pin| pin.set_high());
+        while let Ok((origin, line)) = line_rx.recv().await {
+            let status = Status {
+                uptime_s: Mono::now().ticks() / (TICKS_PER_US * 1_000_000),
+                reports: reports.lock(|reports| reports.stats()),
+            };
+            let was_learning = learning.lock(|learning| learning.is_some());
+            // erase, if needed, is done before locking the resources shared with `on_usb`
+            if protocol::parse(line.as_str()) == Ok(Command::Save) {
+                store.lock(Store::make_room).ok();
+            }
+
+            (
+                &mut console,
+                &mut config_report,
+                &mut settings,
+                &mut mode,
+                &mut learning,
+                &mut store,
+            )
+                .lock(|console, config_report, settings, mode, learning, store| {
+                    let text = line.as_str();
+                    match origin {
+                        Origin::Console => {
+                            console::execute(console, text, status, settings, mode, learning, store)
+                        }
+                        Origin::ConfigReport => console::execute(
+                            config_report,
+                            text,
+                            status,
+                            settings,
+                            mode,
+                            learning,
+                            store,
+                        ),
+                    }
+                });
+
+            let is_learning = learning.lock(|learning| learning.is_some());
+            if is_learning && !was_learning {
+                learning_task::spawn().ok();
+            }
+
+            // LED blinks while learning, otherwise it shows whether reception is enabled
+            if !is_learning {
+                let enabled = settings.lock(|settings| settings.enabled);
+                led.lock(|pin| {
+                    if enabled {
+                        pin.set_low();
                     } else {
-                        defmt::println!("enabled");
-                        *enabled = true;
-                        led.lock(|pin| pin.set_low());
+                        pin.set_high();
                     }
+                });
+            }
+        }
+    }
+
+    #[task(priority=1, shared = [console, settings, learning, led])]
+    async fn learning_task(ctx: learning_task::Context) {
+        let mut console = ctx.shared.console;
+        let mut settings = ctx.shared.settings;
+        let mut learning = ctx.shared.learning;
+        let mut led = ctx.shared.led;
+
+        defmt::println!("learning started");
+        console.lock(|console| console.log(format_args!("learning started")));
+        loop {
+            led.lock(|pin| pin.toggle());
+            DelayNs::delay_ms(&mut Mono, LEARNING_BLINK_MS).await;
+
+            let finished = learning.lock(|learning| match learning {
+                Some(session) if session.target().is_some() => None,
+                Some(session) => {
+                    let map = *session.map();
+                    *learning = None;
+                    Some(Some(map))
                 }
+                None => Some(None),
             });
+
+            match finished {
+                Some(Some(map)) => {
+                    defmt::println!("learning finished");
+                    console.lock(|console| console.log(format_args!("{}", LEARNING_FINISHED)));
+                    settings.lock(|settings| settings.button_map = map);
+                    save_task::spawn().ok();
+                    break;
+                }
+                Some(None) => {
+                    defmt::println!("learning aborted");
+                    console.lock(|console| console.log(format_args!("{}", LEARNING_ABORTED)));
+                    break;
+                }
+                None => {}
+            }
+        }
+
+        settings.lock(|settings| {
+            led.lock(|pin| {
+                if settings.enabled {
+                    pin.set_low();
+                } else {
+                    pin.set_high();
+                }
+            })
+        });
+    }
+
+    #[task(priority=1, shared = [console, settings, store])]
+    async fn save_task(ctx: save_task::Context) {
+        let mut console = ctx.shared.console;
+        let mut settings = ctx.shared.settings;
+        let mut store = ctx.shared.store;
+
+        // bank is erased first, so that the settings are locked only to be copied
+        let saved = store.lock(Store::make_room).and_then(|()| {
+            let current = settings.lock(|settings| *settings);
+            store.lock(|store| store.save(&current))
         });
+        match saved {
+            Ok(()) => {
+                defmt::println!("settings saved");
+                console.lock(|console| console.log(format_args!("settings saved")));
+            }
+            Err(error) => {
+                defmt::println!("saving settings failed: {}", error);
+                console.lock(|console| {
+                    console.log(format_args!("saving settings failed: {:?}", error))
+                });
+            }
+        }
+    }
+
+    #[task(binds = EXTI0, local = [pressed_at : u64 = 0], shared = [btn, led, console, settings, learning])]
+    fn on_btn(ctx: on_btn::Context) {
+        let pressed_at = ctx.local.pressed_at;
+        let mut btn = ctx.shared.btn;
+        let mut led = ctx.shared.led;
+        let mut console = ctx.shared.console;
+        let mut settings = ctx.shared.settings;
+        let mut learning = ctx.shared.learning;
+
+        btn.lock(ExtiPin::clear_interrupt_pending_bit);
+        let ticks = Mono::now().ticks();
+
+        if btn.lock(|btn| btn.is_low()) {
+            *pressed_at = ticks;
+        } else if ticks.wrapping_sub(*pressed_at) >= LEARNING_HOLD {
+            // long press starts learning, or aborts it if already started
+            let started = learning.lock(|learning| {
+                if learning.is_some() {
+                    *learning = None;
+                    false
+                } else {
+                    *learning = Some(Learning::new());
+                    true
+                }
+            });
+            if started {
+                learning_task::spawn().ok();
+            }
+        } else if learning.lock(|learning| learning.as_mut().map(Learning::skip).is_some()) {
+            defmt::println!("skipped");
+        } else {
+            let enabled = settings.lock(|settings| {
+                settings.enabled = !settings.enabled;
+                settings.enabled
+            });
+            if enabled {
+                defmt::println!("enabled");
+                console.lock(|console| console.log(format_args!("enabled")));
+                led.lock(|pin| pin.set_low());
+            } else {
+                defmt::println!("disabled");
+                console.lock(|console| console.log(format_args!("disabled")));
+                led.lock(|pin| pin.set_high());
+            }
+            save_task::spawn().ok();
+        }
         cortex_m::asm::delay(DEBOUNCE_DELAY);
     }
 }
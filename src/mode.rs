@@ -1,4 +1,18 @@
+#[derive(Clone, Copy, PartialEq, Eq, Debug, defmt::Format)]
 pub enum DeviceMode {
     Mouse,
     Keyboard,
+    // pointer positioned by jumps over a grid of the screen, see `Grid`
+    Absolute,
+    // standard gamepad for games and launchers of Android TV
+    Gamepad,
+}
+
+impl DeviceMode {
+    pub const ALL: [DeviceMode; 4] = [
+        DeviceMode::Mouse,
+        DeviceMode::Keyboard,
+        DeviceMode::Absolute,
+        DeviceMode::Gamepad,
+    ];
 }
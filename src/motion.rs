@@ -0,0 +1,73 @@
+use crate::acceleration::{accumulate, Acceleration, SUBPIXELS};
+use crate::config::MOTION_INTERVAL_MS;
+use crate::output::Report;
+
+// Motion of the pointer while a direction button of the remote is held. The remote repeats
+// its frames only every ~100 ms, so the pointer is moved by `motion_task` every
+// `MOTION_INTERVAL_MS` instead, with the speed growing over the time of the motion.
+pub struct Motion {
+    // direction along x and y, `None` while the pointer stands still
+    direction: Option<[i8; 2]>,
+    // mouse buttons pressed during the motion, so that it can drag
+    buttons: u8,
+    precise: bool,
+    elapsed_ms: u32,
+    // fractions of a pixel [1/SUBPIXELS px] moved along x and y, but not reported yet
+    remainder: [i32; 2],
+}
+
+impl Motion {
+    pub const fn new() -> Self {
+        Motion {
+            direction: None,
+            buttons: 0,
+            precise: false,
+            elapsed_ms: 0,
+            remainder: [0, 0],
+        }
+    }
+
+    // Starts the motion in the direction, or keeps it going if it already does.
+    pub fn start(&mut self, direction: [i8; 2], buttons: u8, precise: bool) {
+        if self.direction != Some(direction) {
+            self.direction = Some(direction);
+            self.elapsed_ms = 0;
+            self.remainder = [0, 0];
+        }
+        self.buttons = buttons;
+        self.precise = precise;
+    }
+
+    pub fn stop(&mut self) {
+        self.direction = None;
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.direction.is_some()
+    }
+
+    // Advances the motion by one interval. Returns `None` once the motion has stopped,
+    // no report if the pointer hasn't moved by a whole pixel yet.
+    pub fn step(&mut self, acceleration: &Acceleration) -> Option<Option<Report>> {
+        let [x, y] = self.direction?;
+        let speed = acceleration.speed(self.elapsed_ms, self.precise);
+        self.elapsed_ms = self.elapsed_ms.saturating_add(MOTION_INTERVAL_MS);
+
+        let x = accumulate(&mut self.remainder[0], x, speed, SUBPIXELS);
+        let y = accumulate(&mut self.remainder[1], y, speed, SUBPIXELS);
+        let report = Report::Mouse {
+            buttons: self.buttons,
+            x,
+            y,
+            wheel: 0,
+            pan: 0,
+        };
+        Some((x != 0 || y != 0).then_some(report))
+    }
+}
+
+impl Default for Motion {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,67 @@
+use stm32f4xx_hal::otg_fs::UsbBusType;
+use usb_device::class::{ControlIn, ControlOut, UsbClass};
+
+use crate::config_report::{is_feature_report, HID_REQ_GET_REPORT, HID_REQ_SET_REPORT};
+use crate::reports::{MouseReportEx, ReportId, RESOLUTION_MULTIPLIER};
+
+// Resolution Multipliers of wheel and pan, exchanged through the feature report of the mouse.
+// Hosts which support high-resolution scrolling enable them, others leave them disabled
+// and expect whole detents. Like `ConfigReport`, this class is polled before `HIDClass`.
+pub struct ResolutionMultiplier {
+    // values of the feature report for wheel and pan: 0 - disabled, 1 - enabled
+    enabled: [u8; 2],
+}
+
+impl ResolutionMultiplier {
+    pub const fn new() -> Self {
+        ResolutionMultiplier { enabled: [0, 0] }
+    }
+
+    // Units per detent of wheel and pan, as understood by the host.
+    pub fn multipliers(&self) -> [u8; 2] {
+        self.enabled.map(|enabled| match enabled {
+            0 => 1,
+            _ => RESOLUTION_MULTIPLIER,
+        })
+    }
+}
+
+impl Default for ResolutionMultiplier {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl UsbClass<UsbBusType> for ResolutionMultiplier {
+    // hosts enable the multipliers again after enumeration
+    fn reset(&mut self) {
+        self.enabled = [0, 0];
+    }
+
+    fn control_in(&mut self, xfer: ControlIn<UsbBusType>) {
+        let request = xfer.request();
+        if request.request != HID_REQ_GET_REPORT || !is_feature_report(request, MouseReportEx::ID) {
+            return;
+        }
+
+        let [wheel, pan] = self.enabled;
+        xfer.accept_with(&[MouseReportEx::ID, wheel, pan]).ok();
+    }
+
+    fn control_out(&mut self, xfer: ControlOut<UsbBusType>) {
+        let request = xfer.request();
+        if request.request != HID_REQ_SET_REPORT || !is_feature_report(request, MouseReportEx::ID) {
+            return;
+        }
+
+        match *xfer.data() {
+            [MouseReportEx::ID, wheel, pan] => {
+                self.enabled = [wheel.min(1), pan.min(1)];
+                xfer.accept().ok();
+            }
+            _ => {
+                xfer.reject().ok();
+            }
+        }
+    }
+}
@@ -0,0 +1,100 @@
+use crate::ir::{Frame, Timing};
+use crate::remote::RcButton;
+
+// NEC protocol, timing is given in [us]:
+// - leader: 9 ms mark, 4.5 ms space,
+// - 32 data bits sent LSB first: address, !address, command, !command,
+// - each bit: 562 us mark, followed by 562 us (0) or 1687 us (1) space,
+// - stop mark.
+// Held button sends a repeat frame: 9 ms mark, 2.25 ms space, stop mark.
+// Extended NEC replaces the inverted address by the upper byte of a 16-bit address.
+pub const LEADER_MARK: Timing = Timing::new(9000, 1000);
+const LEADER_SPACE: Timing = Timing::new(4500, 700);
+const REPEAT_SPACE: Timing = Timing::new(2250, 500);
+const BIT_MARK: Timing = Timing::new(562, 250);
+const ZERO_SPACE: Timing = Timing::new(562, 250);
+const ONE_SPACE: Timing = Timing::new(1687, 400);
+const DATA_WIDTH: usize = 32;
+
+pub enum NecFrame {
+    Code { address: u16, command: u8 },
+    Repeat,
+}
+
+pub fn decode_frame(frame: &Frame) -> Option<NecFrame> {
+    let durations = frame.durations();
+    if durations.len() < 3 || !LEADER_MARK.matches(durations[0]) {
+        return None;
+    }
+
+    if REPEAT_SPACE.matches(durations[1]) {
+        return BIT_MARK.matches(durations[2]).then_some(NecFrame::Repeat);
+    }
+
+    if !LEADER_SPACE.matches(durations[1]) || durations.len() < 2 * DATA_WIDTH + 3 {
+        return None;
+    }
+
+    let mut data: u32 = 0;
+    for bit in 0..DATA_WIDTH {
+        let mark = durations[2 + 2 * bit];
+        let space = durations[3 + 2 * bit];
+        if !BIT_MARK.matches(mark) {
+            return None;
+        }
+        if ONE_SPACE.matches(space) {
+            data |= 1 << bit;
+        } else if !ZERO_SPACE.matches(space) {
+            return None;
+        }
+    }
+    if !BIT_MARK.matches(durations[2 + 2 * DATA_WIDTH]) {
+        return None;
+    }
+
+    let [address_low, address_high, command, command_inv] = data.to_le_bytes();
+    if command != !command_inv {
+        return None;
+    }
+
+    let address = if address_low == !address_high {
+        u16::from(address_low)
+    } else {
+        u16::from_le_bytes([address_low, address_high])
+    };
+
+    Some(NecFrame::Code { address, command })
+}
+
+// Common 21-key NEC remote (address 0x00). Digits around 5 move the pointer
+// in the same directions as the arrows and diagonal buttons of DV-MLG-20.
+const KEYMAP: [(u16, u8, RcButton); 21] = [
+    (0x00, 0x18, RcButton::Up),         // 2
+    (0x00, 0x52, RcButton::Down),       // 8
+    (0x00, 0x08, RcButton::Left),       // 4
+    (0x00, 0x5a, RcButton::Right),      // 6
+    (0x00, 0x1c, RcButton::Ok),         // 5
+    (0x00, 0x0c, RcButton::Text),       // 1
+    (0x00, 0x5e, RcButton::MyApps),     // 3
+    (0x00, 0x42, RcButton::Back),       // 7
+    (0x00, 0x4a, RcButton::Exit),       // 9
+    (0x00, 0x16, RcButton::Netflix),    // 0
+    (0x00, 0x19, RcButton::Amazon),     // 100+
+    (0x00, 0x0d, RcButton::Start),      // 200+
+    (0x00, 0x15, RcButton::VolumeUp),   // +
+    (0x00, 0x07, RcButton::VolumeDown), // -
+    (0x00, 0x47, RcButton::PageUp),     // CH+
+    (0x00, 0x45, RcButton::PageDown),   // CH-
+    (0x00, 0x46, RcButton::Red),        // CH
+    (0x00, 0x09, RcButton::Green),      // EQ
+    (0x00, 0x44, RcButton::PrevTrack),  // |<<
+    (0x00, 0x40, RcButton::NextTrack),  // >>|
+    (0x00, 0x43, RcButton::Play),       // >||
+];
+
+pub fn lookup(address: u16, command: u8) -> Option<RcButton> {
+    KEYMAP
+        .iter()
+        .find(|(a, c, _)| *a == address && *c == command)
+        .map(|(_, _, button)| *button)
+}
@@ -0,0 +1,254 @@
+use usb_device::{bus::UsbBus, Result, UsbError};
+use usbd_hid::descriptor::KeyboardUsage;
+use usbd_hid::hid_class::HIDClass;
+
+use crate::config::REPORT_QUEUE_LEN;
+use crate::consumer::ConsumerUsage;
+use crate::gamepad;
+use crate::keyboard;
+use crate::mouse;
+
+pub const MAX_STEPS: usize = 16;
+
+// Report to be sent to the host.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Report {
+    Mouse {
+        buttons: u8,
+        x: i8,
+        y: i8,
+        wheel: i8,
+        pan: i8,
+    },
+    Absolute {
+        buttons: u8,
+        x: u16,
+        y: u16,
+    },
+    Key(KeyboardUsage),
+    ReleaseKeys,
+    Consumer(ConsumerUsage),
+    ReleaseConsumer,
+    Gamepad {
+        buttons: u16,
+        hat: u8,
+    },
+    ReleaseGamepad,
+}
+
+impl Report {
+    pub const fn buttons(buttons: u8) -> Self {
+        Report::Mouse {
+            buttons,
+            x: 0,
+            y: 0,
+            wheel: 0,
+            pan: 0,
+        }
+    }
+
+    // Reports that bring the host to rest: released keys, or buttons without any motion.
+    // Losing one of them leaves a key or a button stuck.
+    pub fn is_priority(&self) -> bool {
+        match *self {
+            Report::Mouse {
+                x, y, wheel, pan, ..
+            } => x == 0 && y == 0 && wheel == 0 && pan == 0,
+            Report::ReleaseKeys | Report::ReleaseConsumer | Report::ReleaseGamepad => true,
+            Report::Absolute { .. }
+            | Report::Key(_)
+            | Report::Consumer(_)
+            | Report::Gamepad { .. } => false,
+        }
+    }
+
+    // Reports of the same kind share a report ID, each of them replaces the state
+    // set by the previous one.
+    fn is_same_kind(&self, other: &Report) -> bool {
+        use Report::*;
+        matches!(
+            (self, other),
+            (Mouse { .. }, Mouse { .. })
+                | (Absolute { .. }, Absolute { .. })
+                | (Key(_) | ReleaseKeys, Key(_) | ReleaseKeys)
+                | (Consumer(_) | ReleaseConsumer, Consumer(_) | ReleaseConsumer)
+                | (
+                    Gamepad { .. } | ReleaseGamepad,
+                    Gamepad { .. } | ReleaseGamepad
+                )
+        )
+    }
+
+    pub fn send<B: UsbBus>(&self, hid: &mut HIDClass<'_, B>) -> Result<usize> {
+        match *self {
+            Report::Mouse {
+                buttons,
+                x,
+                y,
+                wheel,
+                pan,
+            } => mouse::send_report(hid, buttons, x, y, wheel, pan),
+            Report::Absolute { buttons, x, y } => mouse::send_absolute_report(hid, buttons, x, y),
+            Report::Key(key) => keyboard::send_key(hid, key),
+            Report::ReleaseKeys => keyboard::release_keys(hid),
+            Report::Consumer(usage) => keyboard::send_consumer(hid, usage),
+            Report::ReleaseConsumer => keyboard::release_consumer(hid),
+            Report::Gamepad { buttons, hat } => gamepad::send_report(hid, buttons, hat),
+            Report::ReleaseGamepad => gamepad::release(hid),
+        }
+    }
+}
+
+// Report sent `delay_ms` after the previous one.
+#[derive(Clone, Copy)]
+pub struct Step {
+    pub delay_ms: u32,
+    pub report: Report,
+}
+
+// Reports scheduled for a single event of the remote control. They are handed over
+// to `output_task`, which waits between them without holding up anything else.
+pub struct Steps {
+    steps: [Step; MAX_STEPS],
+    len: usize,
+    delay_ms: u32,
+}
+
+impl Steps {
+    pub const fn new() -> Self {
+        Steps {
+            steps: [Step {
+                delay_ms: 0,
+                report: Report::ReleaseKeys,
+            }; MAX_STEPS],
+            len: 0,
+            delay_ms: 0,
+        }
+    }
+
+    // Delays the next report. Delays add up until a report is pushed.
+    pub fn wait(&mut self, delay_ms: u32) {
+        self.delay_ms = self.delay_ms.saturating_add(delay_ms);
+    }
+
+    // Reports beyond `MAX_STEPS` are dropped.
+    pub fn push(&mut self, report: Report) {
+        if let Some(step) = self.steps.get_mut(self.len) {
+            *step = Step {
+                delay_ms: core::mem::take(&mut self.delay_ms),
+                report,
+            };
+            self.len += 1;
+        }
+    }
+
+    pub fn as_slice(&self) -> &[Step] {
+        &self.steps[..self.len]
+    }
+}
+
+impl Default for Steps {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct ReportStats {
+    pub sent: u32,
+    // attempts deferred, because the endpoint was busy
+    pub blocked: u32,
+    // reports dropped, because the queue was full
+    pub dropped: u32,
+    // reports rejected by the USB stack
+    pub failed: u32,
+}
+
+// Reports waiting for the IN endpoint, in the order they are sent. A report is removed
+// only once it has been accepted, so a busy endpoint just delays it until `on_usb`
+// drains the queue again.
+pub struct ReportQueue {
+    reports: [Report; REPORT_QUEUE_LEN],
+    len: usize,
+    stats: ReportStats,
+}
+
+impl ReportQueue {
+    pub const fn new() -> Self {
+        ReportQueue {
+            reports: [Report::ReleaseKeys; REPORT_QUEUE_LEN],
+            len: 0,
+            stats: ReportStats {
+                sent: 0,
+                blocked: 0,
+                dropped: 0,
+                failed: 0,
+            },
+        }
+    }
+
+    pub fn stats(&self) -> ReportStats {
+        self.stats
+    }
+
+    // When the queue is full, a priority report takes the place of the latest ordinary one,
+    // an ordinary report is dropped. If all of them are priority reports, the oldest one
+    // superseded by a later report of its kind, queued or the new one, makes room.
+    // There are fewer kinds than entries, so a priority report is never dropped.
+    pub fn push(&mut self, report: Report) {
+        if self.len == REPORT_QUEUE_LEN {
+            let queued = &self.reports[..self.len];
+            let victim = if report.is_priority() {
+                queued
+                    .iter()
+                    .rposition(|queued| !queued.is_priority())
+                    .or_else(|| {
+                        queued.iter().enumerate().position(|(index, old)| {
+                            old.is_same_kind(&report)
+                                || queued[index + 1..].iter().any(|new| new.is_same_kind(old))
+                        })
+                    })
+            } else {
+                None
+            };
+            self.stats.dropped = self.stats.dropped.wrapping_add(1);
+            match victim {
+                Some(index) => self.remove(index),
+                None => return,
+            }
+        }
+        self.reports[self.len] = report;
+        self.len += 1;
+    }
+
+    // Sends queued reports until the endpoint gets busy.
+    pub fn flush<B: UsbBus>(&mut self, hid: &mut HIDClass<'_, B>) {
+        self.flush_with(|report| report.send(hid));
+    }
+
+    // Passes queued reports to `send` until it reports that the endpoint is busy.
+    pub fn flush_with(&mut self, mut send: impl FnMut(&Report) -> Result<usize>) {
+        while self.len > 0 {
+            match send(&self.reports[0]) {
+                Ok(_) => self.stats.sent = self.stats.sent.wrapping_add(1),
+                Err(UsbError::WouldBlock) => {
+                    self.stats.blocked = self.stats.blocked.wrapping_add(1);
+                    return;
+                }
+                Err(_) => self.stats.failed = self.stats.failed.wrapping_add(1),
+            }
+            self.remove(0);
+        }
+    }
+
+    fn remove(&mut self, index: usize) {
+        self.reports.copy_within(index + 1..self.len, index);
+        self.len -= 1;
+    }
+}
+
+impl Default for ReportQueue {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,99 @@
+use crate::ir::{Frame, Slots, Timing};
+use crate::remote::RcButton;
+
+// Philips RC5 protocol, bi-phase coded with bit time of 1778 us:
+// - 1 is a space followed by a mark, 0 is a mark followed by a space,
+// - 14 bits sent MSB first: start (1), field (inverted command bit 6), toggle,
+//   5 address bits, 6 command bits.
+// Held button repeats the whole frame every 114 ms with the same toggle bit.
+// Toggle bit flips on each new press of a button.
+const UNIT: u32 = 889;
+const TOLERANCE: u32 = 300;
+const FRAME_WIDTH: u32 = 14;
+
+// The first mark is the second half of the start bit, joined by the first half
+// of the field bit if that is 0.
+const LEADERS: [Timing; 2] = [
+    Timing::new(UNIT, TOLERANCE),
+    Timing::new(2 * UNIT, TOLERANCE),
+];
+
+pub struct Rc5Frame {
+    pub address: u8,
+    pub command: u8,
+    pub toggle: bool,
+}
+
+// Tells if the marks and spaces may be a frame, no matter how they vary: in some codes,
+// e.g. 1s followed by 0s, all but one of them last a single unit.
+pub fn may_be_frame(durations: &[u32]) -> bool {
+    let fits = durations.len() < 2 * FRAME_WIDTH as usize;
+    fits && durations
+        .first()
+        .is_some_and(|&leader| LEADERS.iter().any(|timing| timing.matches(leader)))
+}
+
+pub fn decode_frame(frame: &Frame) -> Option<Rc5Frame> {
+    let slots = Slots::new(frame, UNIT, TOLERANCE)?;
+    // the first half of the start bit is a space lost in the preceding idle state
+    if slots.count() > 2 * FRAME_WIDTH - 1 {
+        return None;
+    }
+
+    let mut data: u16 = 0;
+    for bit in 0..FRAME_WIDTH {
+        let first = bit > 0 && slots.is_mark(2 * bit - 1);
+        let second = slots.is_mark(2 * bit);
+        if first == second {
+            return None;
+        }
+        data = (data << 1) | u16::from(second);
+    }
+
+    let field = data & (1 << 12) != 0;
+    let toggle = data & (1 << 11) != 0;
+    let address = ((data >> 6) & 0x1f) as u8;
+    let mut command = (data & 0x3f) as u8;
+    if !field {
+        command |= 0x40;
+    }
+
+    Some(Rc5Frame {
+        address,
+        command,
+        toggle,
+    })
+}
+
+// Philips TV (address 0x00). Digits around 5 move the pointer
+// in the same directions as the diagonal buttons of DV-MLG-20.
+const KEYMAP: [(u8, u8, RcButton); 21] = [
+    (0x00, 0x50, RcButton::Up),
+    (0x00, 0x51, RcButton::Down),
+    (0x00, 0x55, RcButton::Left),
+    (0x00, 0x56, RcButton::Right),
+    (0x00, 0x57, RcButton::Ok),
+    (0x00, 0x01, RcButton::Text),   // 1
+    (0x00, 0x03, RcButton::MyApps), // 3
+    (0x00, 0x07, RcButton::Back),   // 7
+    (0x00, 0x09, RcButton::Exit),   // 9
+    (0x00, 0x10, RcButton::VolumeUp),
+    (0x00, 0x11, RcButton::VolumeDown),
+    (0x00, 0x0d, RcButton::Mute),
+    (0x00, 0x20, RcButton::PageUp),   // channel +
+    (0x00, 0x21, RcButton::PageDown), // channel -
+    (0x00, 0x6b, RcButton::Red),
+    (0x00, 0x6c, RcButton::Green),
+    (0x00, 0x35, RcButton::Play),
+    (0x00, 0x30, RcButton::Pause),
+    (0x00, 0x36, RcButton::Stop),
+    (0x00, 0x32, RcButton::PrevTrack), // rewind
+    (0x00, 0x34, RcButton::NextTrack), // fast forward
+];
+
+pub fn lookup(address: u8, command: u8) -> Option<RcButton> {
+    KEYMAP
+        .iter()
+        .find(|(a, c, _)| *a == address && *c == command)
+        .map(|(_, _, button)| *button)
+}
@@ -0,0 +1,110 @@
+use crate::ir::{Frame, Slots, Timing};
+use crate::remote::RcButton;
+
+// Philips RC6 protocol, mode 0, bi-phase coded with unit of 444 us:
+// - leader: 6 units of mark, 2 units of space,
+// - 1 is a mark followed by a space, 0 is a space followed by a mark,
+// - start bit (1), 3 mode bits, trailer (toggle) bit of double length,
+// - 8 address bits, 8 command bits, sent MSB first.
+// Held button repeats the whole frame with the same toggle bit.
+// Toggle bit flips on each new press of a button.
+const UNIT: u32 = 444;
+const TOLERANCE: u32 = 180;
+const LEADER_MARK: u32 = 6;
+const LEADER_SPACE: u32 = 2;
+const START: u32 = LEADER_MARK + LEADER_SPACE;
+const MODE: u32 = START + 2;
+const TRAILER: u32 = MODE + 3 * 2;
+const DATA: u32 = TRAILER + 4;
+const DATA_WIDTH: u32 = 16;
+pub const LEADER: Timing = Timing::new(LEADER_MARK * UNIT, TOLERANCE);
+
+pub struct Rc6Frame {
+    pub address: u8,
+    pub command: u8,
+    pub toggle: bool,
+}
+
+// Returns value of the bit spanning `2 * width` slots starting from `index`.
+fn read_bit(slots: &Slots, index: u32, width: u32) -> Option<bool> {
+    let first = slots.is_mark(index);
+    let second = slots.is_mark(index + width);
+    for offset in 1..width {
+        if slots.is_mark(index + offset) != first || slots.is_mark(index + width + offset) != second
+        {
+            return None;
+        }
+    }
+    (first != second).then_some(first)
+}
+
+pub fn decode_frame(frame: &Frame) -> Option<Rc6Frame> {
+    let slots = Slots::new(frame, UNIT, TOLERANCE)?;
+    if slots.count() > DATA + 2 * DATA_WIDTH {
+        return None;
+    }
+
+    if !(0..LEADER_MARK).all(|index| slots.is_mark(index))
+        || (LEADER_MARK..START).any(|index| slots.is_mark(index))
+    {
+        return None;
+    }
+
+    if !read_bit(&slots, START, 1)? {
+        return None;
+    }
+
+    let mut mode: u8 = 0;
+    for bit in 0..3 {
+        mode = (mode << 1) | u8::from(read_bit(&slots, MODE + 2 * bit, 1)?);
+    }
+    if mode != 0 {
+        return None;
+    }
+
+    let toggle = read_bit(&slots, TRAILER, 2)?;
+
+    let mut data: u16 = 0;
+    for bit in 0..DATA_WIDTH {
+        data = (data << 1) | u16::from(read_bit(&slots, DATA + 2 * bit, 1)?);
+    }
+    let [command, address] = data.to_le_bytes();
+
+    Some(Rc6Frame {
+        address,
+        command,
+        toggle,
+    })
+}
+
+// Philips TV / media center (address 0x00).
+const KEYMAP: [(u8, u8, RcButton); 21] = [
+    (0x00, 0x58, RcButton::Up),
+    (0x00, 0x59, RcButton::Down),
+    (0x00, 0x5a, RcButton::Left),
+    (0x00, 0x5b, RcButton::Right),
+    (0x00, 0x5c, RcButton::Ok),
+    (0x00, 0x0a, RcButton::Back),
+    (0x00, 0x54, RcButton::MyApps), // menu
+    (0x00, 0x83, RcButton::Exit),
+    (0x00, 0x10, RcButton::VolumeUp),
+    (0x00, 0x11, RcButton::VolumeDown),
+    (0x00, 0x0d, RcButton::Mute),
+    (0x00, 0x4c, RcButton::PageUp),   // channel +
+    (0x00, 0x4d, RcButton::PageDown), // channel -
+    (0x00, 0x6d, RcButton::Red),
+    (0x00, 0x6e, RcButton::Green),
+    (0x00, 0x2c, RcButton::Play),
+    (0x00, 0x30, RcButton::Pause),
+    (0x00, 0x31, RcButton::Stop),
+    (0x00, 0x37, RcButton::Record),
+    (0x00, 0x29, RcButton::PrevTrack), // rewind
+    (0x00, 0x28, RcButton::NextTrack), // fast forward
+];
+
+pub fn lookup(address: u8, command: u8) -> Option<RcButton> {
+    KEYMAP
+        .iter()
+        .find(|(a, c, _)| *a == address && *c == command)
+        .map(|(_, _, button)| *button)
+}
@@ -1,4 +1,5 @@
-use int_enum::IntEnum;
+use crate::config::*;
+use crate::ir::{Frame, Timing};
 
 // keycode format: FF0UUUUULLLLLLLL
 // each letter is a nibble where:
@@ -12,40 +13,192 @@ pub const DATA_WIDTH: u32 = UPPER_WIDTH + LOWER_WIDTH;
 pub fn decode_keycode(keycode: u64) -> (u32, u32, bool) {
     let flags: u8 = (keycode >> FLAGS_OFFSET).try_into().unwrap();
     let flag_repeated = 0x1 & flags != 0;
-    let upper_code: u32 = (0x000fffff & (keycode >> 8 * NIBBLE_WIDTH))
+    let upper_code: u32 = (0x000fffff & (keycode >> (8 * NIBBLE_WIDTH)))
         .try_into()
         .unwrap();
     let lower_code: u32 = (keycode & 0xffffffff).try_into().unwrap();
-    return (upper_code, lower_code, flag_repeated);
+    (upper_code, lower_code, flag_repeated)
 }
 
-#[repr(u32)]
-#[derive(IntEnum)]
+// Timing of DV-MLG-20 frames, adjustable at runtime.
+#[derive(Clone, Copy)]
+pub struct Sampling {
+    pub preamble: Timing,
+    pub offset_us: u32,
+    pub interval_us: u32,
+    pub tolerance_us: u32,
+}
+
+impl Sampling {
+    pub const fn new() -> Self {
+        Sampling {
+            preamble: Timing::new(PREAMBLE_REFERENCE_US, PREAMBLE_TOLERANCE_US),
+            offset_us: SAMPLE_OFFSET_US,
+            interval_us: SAMPLE_INTERVAL_US,
+            tolerance_us: SAMPLE_TOLERANCE_US,
+        }
+    }
+
+    // Number of bit slots covered by a run of constant level.
+    fn count_slots(&self, duration: u32) -> Option<u32> {
+        let slots = duration.saturating_add(self.interval_us / 2) / self.interval_us;
+        if duration.abs_diff(slots * self.interval_us) <= self.tolerance_us {
+            Some(slots)
+        } else {
+            None
+        }
+    }
+}
+
+impl Default for Sampling {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Converts a captured frame into a keycode (without flags).
+// Frame starts with the preamble mark. Each following run of constant level
+// covers an integer number of bit slots, where space is read as 1 and mark as 0.
+// Counting slots per run, instead of sampling at fixed instants,
+// makes decoding immune to the clock drift of the remote control.
+pub fn decode_frame(frame: &Frame, sampling: &Sampling) -> Option<u64> {
+    let (&preamble, runs) = frame.durations().split_first()?;
+    if !sampling.preamble.matches(preamble) {
+        return None;
+    }
+
+    // first slot begins half of the interval before the first sample
+    let lead_in = sampling.offset_us.saturating_sub(sampling.interval_us / 2);
+    let mut keycode: u64 = 0;
+    let mut width: u32 = 0;
+    let mut level = true;
+
+    for (index, &duration) in runs.iter().enumerate() {
+        let slots = if index == 0 {
+            if duration.saturating_add(sampling.tolerance_us) < lead_in {
+                return None;
+            }
+            sampling.count_slots(duration.saturating_sub(lead_in))?
+        } else {
+            match sampling.count_slots(duration)? {
+                0 => return None,
+                slots => slots,
+            }
+        };
+
+        for _ in 0..slots.min(DATA_WIDTH - width) {
+            keycode = (keycode << 1) | u64::from(level);
+            width += 1;
+        }
+        level = !level;
+    }
+
+    // line stays at the level following the last edge
+    while width < DATA_WIDTH {
+        keycode = (keycode << 1) | u64::from(level);
+        width += 1;
+    }
+
+    Some(keycode)
+}
+
+// Buttons of DV-MLG-20. Codes of other remote controls are translated to these.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, defmt::Format)]
 pub enum RcButton {
-    Up = 0x5012aa97,
-    Down = 0x5408aa97,
-    Left = 0x55401557,
-    Right = 0x52811557,
-    Ok = 0x51094a97,
-    Text = 0x5022aa57,
-    MyApps = 0x52092a97,
-    Back = 0x50915257,
-    Exit = 0x55290897,
-    PageUp = 0x50055557,
-    PageDown = 0x54015557,
-    VolumeUp = 0x52025557,
-    VolumeDown = 0x55005557,
-    Mute = 0x5440a557,
-    Netflix = 0x52924497,
-    Start = 0x51552817,
-    Amazon = 0x51525097,
-    Red = 0x522a4a17,
-    Green = 0x542a2a17,
-    Record = 0x54aa4827,
-    Stop = 0x54292a27,
-    PrevTrack = 0x555082a7,
-    Play = 0x5052aa27,
-    Pause = 0x5254a427,
-    NextTrack = 0x52a142a7,
+    Up,
+    Down,
+    Left,
+    Right,
+    Ok,
+    Text,
+    MyApps,
+    Back,
+    Exit,
+    PageUp,
+    PageDown,
+    VolumeUp,
+    VolumeDown,
+    Mute,
+    Netflix,
+    Start,
+    Amazon,
+    Red,
+    Green,
+    Record,
+    Stop,
+    PrevTrack,
+    Play,
+    Pause,
+    NextTrack,
     // more buttons to come as they are needed
 }
+
+impl RcButton {
+    // all buttons in order of declaration
+    pub const ALL: [RcButton; 25] = [
+        RcButton::Up,
+        RcButton::Down,
+        RcButton::Left,
+        RcButton::Right,
+        RcButton::Ok,
+        RcButton::Text,
+        RcButton::MyApps,
+        RcButton::Back,
+        RcButton::Exit,
+        RcButton::PageUp,
+        RcButton::PageDown,
+        RcButton::VolumeUp,
+        RcButton::VolumeDown,
+        RcButton::Mute,
+        RcButton::Netflix,
+        RcButton::Start,
+        RcButton::Amazon,
+        RcButton::Red,
+        RcButton::Green,
+        RcButton::Record,
+        RcButton::Stop,
+        RcButton::PrevTrack,
+        RcButton::Play,
+        RcButton::Pause,
+        RcButton::NextTrack,
+    ];
+}
+
+// Lower codes of DV-MLG-20, used unless other codes are learned.
+const KEYMAP: [(u32, RcButton); 25] = [
+    (0x5012aa97, RcButton::Up),
+    (0x5408aa97, RcButton::Down),
+    (0x55401557, RcButton::Left),
+    (0x52811557, RcButton::Right),
+    (0x51094a97, RcButton::Ok),
+    (0x5022aa57, RcButton::Text),
+    (0x52092a97, RcButton::MyApps),
+    (0x50915257, RcButton::Back),
+    (0x55290897, RcButton::Exit),
+    (0x50055557, RcButton::PageUp),
+    (0x54015557, RcButton::PageDown),
+    (0x52025557, RcButton::VolumeUp),
+    (0x55005557, RcButton::VolumeDown),
+    (0x5440a557, RcButton::Mute),
+    (0x52924497, RcButton::Netflix),
+    (0x51552817, RcButton::Start),
+    (0x51525097, RcButton::Amazon),
+    (0x522a4a17, RcButton::Red),
+    (0x542a2a17, RcButton::Green),
+    (0x54aa4827, RcButton::Record),
+    (0x54292a27, RcButton::Stop),
+    (0x555082a7, RcButton::PrevTrack),
+    (0x5052aa27, RcButton::Play),
+    (0x5254a427, RcButton::Pause),
+    (0x52a142a7, RcButton::NextTrack),
+];
+
+pub fn lookup(upper_code: u32, lower_code: u32) -> Option<RcButton> {
+    if upper_code != MAGIC_PREFIX {
+        return None;
+    }
+    KEYMAP
+        .iter()
+        .find(|(c, _)| *c == lower_code)
+        .map(|(_, button)| *button)
+}
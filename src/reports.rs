@@ -0,0 +1,203 @@
+// Layout of the HID reports. This module is also compiled into the build script,
+// which assembles `HID_DESCRIPTOR` from descriptors of these structs, so it must not depend
+// on the rest of the crate. Tests of the host check the descriptor against their serializers.
+
+use mickey_protocol::CONFIG_REPORT_ID;
+use usbd_hid::descriptor::generator_prelude::*;
+
+// Wheel and pan move by 1/RESOLUTION_MULTIPLIER of a detent, once the host enables
+// the multiplier. It divides 120, as expected by Windows.
+pub const RESOLUTION_MULTIPLIER: u8 = 12;
+
+// Report ID of each report. The build script inserts it into the descriptor of the report,
+// right after its application collection. Reports are sent with the ID in front by `Tagged`.
+// IDs are kept out of the macro attributes, since the macro doesn't serialize reports with IDs.
+pub trait ReportId {
+    const ID: u8;
+}
+
+// Report preceded by its ID. The ID is stored as well, since serialized reports must not
+// be larger than their structs.
+pub struct Tagged<R> {
+    id: u8,
+    report: R,
+}
+
+impl<R: ReportId> Tagged<R> {
+    pub fn new(report: R) -> Self {
+        Tagged { id: R::ID, report }
+    }
+}
+
+impl<R: Serialize> Serialize for Tagged<R> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_tuple(2)?;
+        s.serialize_element(&self.id)?;
+        s.serialize_element(&self.report)?;
+        s.end()
+    }
+}
+
+impl<R: AsInputReport> AsInputReport for Tagged<R> {}
+
+#[gen_hid_descriptor(
+    (collection = APPLICATION, usage_page = GENERIC_DESKTOP, usage = MOUSE) = {
+        (collection = PHYSICAL, usage = POINTER) = {
+            (usage_page = BUTTON, usage_min = BUTTON_1, usage_max = BUTTON_8) = {
+                #[packed_bits 8] #[item_settings data,variable,absolute] buttons=input;
+            };
+            (usage_page = GENERIC_DESKTOP,) = {
+                (usage = X,) = {
+                    #[item_settings data,variable,relative] x=input;
+                };
+                (usage = Y,) = {
+                    #[item_settings data,variable,relative] y=input;
+                };
+            };
+            // Resolution Multiplier (0x48) applies to the controls of its logical collection.
+            // Its physical extents are added by the build script.
+            (collection = LOGICAL,) = {
+                (usage_page = GENERIC_DESKTOP, usage = 0x48,) = {
+                    #[item_settings data,variable,absolute] wheel_multiplier=feature;
+                };
+                (usage = WHEEL,) = {
+                    #[item_settings data,variable,relative] wheel=input;
+                };
+            };
+            (collection = LOGICAL,) = {
+                (usage_page = GENERIC_DESKTOP, usage = 0x48,) = {
+                    #[item_settings data,variable,absolute] pan_multiplier=feature;
+                };
+                (usage_page = CONSUMER, usage = AC_PAN,) = {
+                    #[item_settings data,variable,relative] pan=input;
+                };
+            };
+        };
+    }
+)]
+pub struct MouseReportEx {
+    pub buttons: u8,
+    pub x: i8,
+    pub y: i8,
+    pub wheel: i8, // Scroll down (negative) or up (positive) this many units
+    pub pan: i8,   // Scroll left (negative) or right (positive) this many units
+    // Feature fields, not a part of the input report. They are exchanged by `ResolutionMultiplier`.
+    pub wheel_multiplier: u8,
+    pub pan_multiplier: u8,
+}
+
+// Pointer with absolute coordinates, positioned by `Grid` in the absolute mode.
+#[gen_hid_descriptor(
+    (collection = APPLICATION, usage_page = GENERIC_DESKTOP, usage = MOUSE) = {
+        (collection = PHYSICAL, usage = POINTER) = {
+            (usage_page = BUTTON, usage_min = BUTTON_1, usage_max = BUTTON_8) = {
+                #[packed_bits 8] #[item_settings data,variable,absolute] buttons=input;
+            };
+            (usage_page = GENERIC_DESKTOP,) = {
+                (usage = X,) = {
+                    #[item_settings data,variable,absolute] x=input;
+                };
+                (usage = Y,) = {
+                    #[item_settings data,variable,absolute] y=input;
+                };
+            };
+        };
+    }
+)]
+pub struct AbsolutePointerReportEx {
+    pub buttons: u8,
+    pub x: u16,
+    pub y: u16,
+}
+
+// Gamepad of the gamepad mode: 16 buttons and a hat switch (0x39), which points
+// clockwise from up in steps of 45°. Logical range of the hat, 0..7, is set by the build
+// script, anything beyond it (`HAT_CENTERED`) is its null state.
+#[gen_hid_descriptor(
+    (collection = APPLICATION, usage_page = GENERIC_DESKTOP, usage = GAMEPAD) = {
+        (usage_page = BUTTON, usage_min = 0x01, usage_max = 0x10) = {
+            #[packed_bits 16] #[item_settings data,variable,absolute] buttons=input;
+        };
+        (usage_page = GENERIC_DESKTOP, usage = 0x39,) = {
+            #[item_settings data,variable,absolute,null] hat=input;
+        };
+    }
+)]
+pub struct GamepadReportEx {
+    // little-endian, the macro can't pack bits of wider fields
+    pub buttons: [u8; 2],
+    pub hat: u8,
+}
+
+#[gen_hid_descriptor(
+    (collection = APPLICATION, usage_page = GENERIC_DESKTOP, usage = KEYBOARD) = {
+        (usage_page = KEYBOARD, usage_min = 0xE0, usage_max = 0xE7) = {
+            #[packed_bits 8] #[item_settings data,variable,absolute] modifier=input;
+        };
+        (usage_min = 0x00, usage_max = 0xFF) = {
+            #[item_settings constant,variable,absolute] reserved=input;
+        };
+        (usage_page = LEDS, usage_min = 0x01, usage_max = 0x05) = {
+            #[packed_bits 5] #[item_settings data,variable,absolute] leds=output;
+        };
+        (usage_page = KEYBOARD, usage_min = 0x00, usage_max = 0xDD) = {
+            #[item_settings data,array,absolute] keycodes=input;
+        };
+    }
+)]
+#[allow(dead_code)]
+pub struct KeyboardReportEx {
+    pub modifier: u8,
+    pub reserved: u8,
+    pub leds: u8,
+    pub keycodes: [u8; 6],
+}
+
+#[gen_hid_descriptor(
+    (collection = APPLICATION, usage_page = CONSUMER, usage = CONSUMER_CONTROL) = {
+        (usage_page = CONSUMER, usage_min = 0x00, usage_max = 0x514) = {
+            #[item_settings data,array,absolute,not_null] usage_id=input;
+        };
+    }
+)]
+pub struct MediaKeyboardReportEx {
+    pub usage_id: u16,
+}
+
+// Vendor-defined configuration report, exchanged only through control transfers
+// by `ConfigReport`, it is described here just to be a part of the descriptor.
+#[gen_hid_descriptor(
+    (collection = APPLICATION, usage_page = VENDOR_DEFINED_START, usage = 0x01) = {
+        (usage = 0x01,) = {
+            data=feature;
+        };
+    }
+)]
+#[allow(dead_code)]
+pub struct ConfigReportEx {
+    pub data: [u8; 63],
+}
+
+impl ReportId for MouseReportEx {
+    const ID: u8 = 1;
+}
+
+impl ReportId for KeyboardReportEx {
+    const ID: u8 = 2;
+}
+
+impl ReportId for MediaKeyboardReportEx {
+    const ID: u8 = 3;
+}
+
+impl ReportId for ConfigReportEx {
+    const ID: u8 = CONFIG_REPORT_ID;
+}
+
+impl ReportId for AbsolutePointerReportEx {
+    const ID: u8 = 5;
+}
+
+impl ReportId for GamepadReportEx {
+    const ID: u8 = 6;
+}
@@ -0,0 +1,405 @@
+use mickey_protocol::Param;
+
+use crate::acceleration::{Acceleration, Curve, SPEED_LIMIT};
+use crate::config::*;
+use crate::decoder::{IrCode, Protocol};
+use crate::ir::Timing;
+use crate::learning::ButtonMap;
+use crate::remote::{RcButton, Sampling};
+use crate::storage::{Storage, StorageError, BANKS};
+
+// Parameters adjustable at runtime, persisted in flash.
+#[derive(Clone, Copy)]
+pub struct Settings {
+    pub sampling: Sampling,
+    pub button_release_delay_ms: u32,
+    pub double_click_delay_ms: u32,
+    pub acceleration: Acceleration,
+    pub button_map: ButtonMap,
+    pub enabled: bool,
+}
+
+impl Settings {
+    pub const fn new() -> Self {
+        Settings {
+            sampling: Sampling::new(),
+            button_release_delay_ms: MOUSE_BUTTON_RELEASE_DELAY_MS,
+            double_click_delay_ms: MOUSE_DOUBLE_CLICK_DELAY_MS,
+            acceleration: Acceleration::new(),
+            button_map: ButtonMap::new(),
+            enabled: true,
+        }
+    }
+
+    pub fn get(&self, param: Param) -> u32 {
+        match param {
+            Param::PreambleReference => self.sampling.preamble.reference,
+            Param::PreambleTolerance => self.sampling.preamble.tolerance,
+            Param::SampleOffset => self.sampling.offset_us,
+            Param::SampleInterval => self.sampling.interval_us,
+            Param::SampleTolerance => self.sampling.tolerance_us,
+            Param::ButtonReleaseDelay => self.button_release_delay_ms,
+            Param::DoubleClickDelay => self.double_click_delay_ms,
+            Param::Curve => self.acceleration.curve as u32,
+            Param::AccelerationTime => self.acceleration.time_ms,
+            Param::MinSpeed => self.acceleration.min_speed,
+            Param::MaxSpeed => self.acceleration.max_speed,
+            Param::MoveStep0 => u32::from(self.acceleration.table[0]),
+            Param::MoveStep1 => u32::from(self.acceleration.table[1]),
+            Param::MoveStep2 => u32::from(self.acceleration.table[2]),
+            Param::MoveStep3 => u32::from(self.acceleration.table[3]),
+            Param::Precision => self.acceleration.precision,
+        }
+    }
+
+    // Returns `false` if the value is not acceptable.
+    pub fn set(&mut self, param: Param, value: u32) -> bool {
+        match param {
+            Param::PreambleReference
+            | Param::PreambleTolerance
+            | Param::SampleOffset
+            | Param::SampleInterval
+            | Param::SampleTolerance
+                if value > MAX_SAMPLING_US =>
+            {
+                return false
+            }
+            Param::PreambleReference => self.sampling.preamble.reference = value,
+            Param::PreambleTolerance => self.sampling.preamble.tolerance = value,
+            Param::SampleOffset => self.sampling.offset_us = value,
+            Param::SampleInterval if value == 0 => return false,
+            Param::SampleInterval => self.sampling.interval_us = value,
+            Param::SampleTolerance => self.sampling.tolerance_us = value,
+            Param::ButtonReleaseDelay | Param::DoubleClickDelay if value > MAX_CLICK_DELAY_MS => {
+                return false
+            }
+            Param::ButtonReleaseDelay => self.button_release_delay_ms = value,
+            Param::DoubleClickDelay => self.double_click_delay_ms = value,
+            Param::Curve => match Curve::ALL.get(value as usize) {
+                Some(&curve) => self.acceleration.curve = curve,
+                None => return false,
+            },
+            Param::AccelerationTime if value > MAX_ACCELERATION_TIME_MS => return false,
+            Param::AccelerationTime => self.acceleration.time_ms = value,
+            Param::MinSpeed | Param::MaxSpeed if value > SPEED_LIMIT => return false,
+            Param::MinSpeed => self.acceleration.min_speed = value,
+            Param::MaxSpeed => self.acceleration.max_speed = value,
+            Param::MoveStep0 | Param::MoveStep1 | Param::MoveStep2 | Param::MoveStep3
+                if value > u32::from(u8::MAX) =>
+            {
+                return false
+            }
+            Param::MoveStep0 => self.acceleration.table[0] = value as u8,
+            Param::MoveStep1 => self.acceleration.table[1] = value as u8,
+            Param::MoveStep2 => self.acceleration.table[2] = value as u8,
+            Param::MoveStep3 => self.acceleration.table[3] = value as u8,
+            Param::Precision if value == 0 => return false,
+            Param::Precision => self.acceleration.precision = value,
+        }
+        true
+    }
+
+    fn encode(&self, writer: &mut Writer) {
+        let sampling = &self.sampling;
+        let acceleration = &self.acceleration;
+        for value in [
+            sampling.preamble.reference,
+            sampling.preamble.tolerance,
+            sampling.offset_us,
+            sampling.interval_us,
+            sampling.tolerance_us,
+            self.button_release_delay_ms,
+            self.double_click_delay_ms,
+            acceleration.time_ms,
+            acceleration.min_speed,
+            acceleration.max_speed,
+            acceleration.precision,
+        ] {
+            writer.put(&value.to_le_bytes());
+        }
+        writer.put(&[acceleration.curve as u8]);
+        writer.put(&acceleration.table);
+        writer.put(&[u8::from(self.enabled)]);
+
+        for button in RcButton::ALL {
+            match self.button_map.get(button) {
+                Some(code) => {
+                    writer.put(&[code.protocol as u8]);
+                    writer.put(&code.address.to_le_bytes());
+                    writer.put(&code.command.to_le_bytes());
+                }
+                None => writer.put(&[UNBOUND; 9]),
+            }
+        }
+    }
+
+    fn decode(reader: &mut Reader) -> Option<Self> {
+        let mut u32_value = || reader.take().map(u32::from_le_bytes);
+        let sampling = Sampling {
+            preamble: Timing::new(u32_value()?, u32_value()?),
+            offset_us: u32_value()?,
+            interval_us: u32_value()?,
+            tolerance_us: u32_value()?,
+        };
+        let button_release_delay_ms = u32_value()?;
+        let double_click_delay_ms = u32_value()?;
+        let time_ms = u32_value()?;
+        let min_speed = u32_value()?;
+        let max_speed = u32_value()?;
+        let precision = u32_value()?;
+        let [curve] = reader.take()?;
+        let acceleration = Acceleration {
+            curve: *Curve::ALL.get(usize::from(curve))?,
+            time_ms,
+            min_speed,
+            max_speed,
+            table: reader.take()?,
+            precision,
+        };
+        let [enabled] = reader.take()?;
+
+        let mut button_map = ButtonMap::new();
+        for button in RcButton::ALL {
+            let [protocol] = reader.take()?;
+            let address = u32::from_le_bytes(reader.take()?);
+            let command = u32::from_le_bytes(reader.take()?);
+            if protocol != UNBOUND {
+                let code = IrCode {
+                    protocol: *Protocol::ALL.get(usize::from(protocol))?,
+                    address,
+                    command,
+                };
+                button_map.set(button, Some(code));
+            }
+        }
+
+        // zero interval would break decoding of DV-MLG-20 frames
+        if sampling.interval_us == 0 {
+            return None;
+        }
+        // timing beyond the range accepted by `set` is rejected as well
+        let timing = [
+            sampling.preamble.reference,
+            sampling.preamble.tolerance,
+            sampling.offset_us,
+            sampling.interval_us,
+            sampling.tolerance_us,
+        ];
+        if timing.into_iter().any(|value| value > MAX_SAMPLING_US) {
+            return None;
+        }
+        // and so are delays, which would hold up the reports
+        if button_release_delay_ms.max(double_click_delay_ms) > MAX_CLICK_DELAY_MS
+            || acceleration.time_ms > MAX_ACCELERATION_TIME_MS
+        {
+            return None;
+        }
+        // neither would zero precision divisor
+        if acceleration.precision == 0 {
+            return None;
+        }
+
+        Some(Settings {
+            sampling,
+            button_release_delay_ms,
+            double_click_delay_ms,
+            acceleration,
+            button_map,
+            enabled: enabled != 0,
+        })
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Record format: magic, version, length of the payload, sequence number, payload,
+// CRC-32 of all the preceding bytes. Records of other versions are ignored,
+// so that defaults apply after incompatible changes.
+const MAGIC: u32 = 0x4d4b_4d53;
+const VERSION: u16 = 4;
+const HEADER_SIZE: usize = 12;
+const CRC_SIZE: usize = 4;
+const SLOT_SIZE: usize = 512;
+const UNBOUND: u8 = 0xff;
+
+struct Writer<'a> {
+    bytes: &'a mut [u8],
+    len: usize,
+}
+
+impl Writer<'_> {
+    fn put(&mut self, data: &[u8]) {
+        self.bytes[self.len..self.len + data.len()].copy_from_slice(data);
+        self.len += data.len();
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl Reader<'_> {
+    fn take<const N: usize>(&mut self) -> Option<[u8; N]> {
+        let (head, tail) = self.bytes.split_first_chunk::<N>()?;
+        self.bytes = tail;
+        Some(*head)
+    }
+}
+
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+// Writes the record into the slot, returns its length.
+fn encode_record(settings: &Settings, sequence: u32, slot: &mut [u8; SLOT_SIZE]) -> usize {
+    let mut writer = Writer {
+        bytes: &mut slot[HEADER_SIZE..SLOT_SIZE - CRC_SIZE],
+        len: 0,
+    };
+    settings.encode(&mut writer);
+    let payload_len = writer.len;
+
+    slot[0..4].copy_from_slice(&MAGIC.to_le_bytes());
+    slot[4..6].copy_from_slice(&VERSION.to_le_bytes());
+    slot[6..8].copy_from_slice(&(payload_len as u16).to_le_bytes());
+    slot[8..12].copy_from_slice(&sequence.to_le_bytes());
+
+    let len = HEADER_SIZE + payload_len;
+    let crc = crc32(&slot[..len]);
+    slot[len..len + CRC_SIZE].copy_from_slice(&crc.to_le_bytes());
+    len + CRC_SIZE
+}
+
+// Returns the sequence number of the record and the settings.
+fn decode_record(slot: &[u8; SLOT_SIZE]) -> Option<(u32, Settings)> {
+    let mut header = Reader { bytes: slot };
+    let magic = u32::from_le_bytes(header.take()?);
+    let version = u16::from_le_bytes(header.take()?);
+    let payload_len = usize::from(u16::from_le_bytes(header.take()?));
+    let sequence = u32::from_le_bytes(header.take()?);
+    if magic != MAGIC || version != VERSION || payload_len > SLOT_SIZE - HEADER_SIZE - CRC_SIZE {
+        return None;
+    }
+
+    let len = HEADER_SIZE + payload_len;
+    let crc = u32::from_le_bytes(slot[len..len + CRC_SIZE].try_into().ok()?);
+    if crc != crc32(&slot[..len]) {
+        return None;
+    }
+
+    let settings = Settings::decode(&mut Reader {
+        bytes: &slot[HEADER_SIZE..len],
+    })?;
+    Some((sequence, settings))
+}
+
+// Log of records in the banks of the storage. Each save appends a new record, leaving
+// the previous one intact, so that a power failure during programming loses only the record
+// being written. When the bank is full, the log continues in the next one, which is erased
+// first. The full bank keeps the latest record until a new one is written, so a power failure
+// during erase doesn't lose it either. Banks are erased only when full, which spreads wear
+// over all of their slots. Records are numbered, the one with the highest number is the latest.
+pub struct Store<S: Storage> {
+    storage: S,
+    bank: usize,
+    next: usize,
+    sequence: u32,
+}
+
+impl<S: Storage> Store<S> {
+    pub fn new(storage: S) -> Self {
+        let mut store = Store {
+            storage,
+            bank: 0,
+            next: 0,
+            sequence: 0,
+        };
+        // log continues in the bank of the latest record
+        if let Some((bank, sequence, _)) = store.latest() {
+            store.bank = bank;
+            store.sequence = sequence;
+        }
+        store.next = store.end(store.bank);
+        store
+    }
+
+    fn is_erased(&self, bank: usize, offset: usize) -> bool {
+        let mut magic = [0; 4];
+        self.storage.read(bank, offset, &mut magic);
+        magic == [0xff; 4]
+    }
+
+    // Offset following the log of the bank.
+    // Records are appended one after another, the first erased slot ends the log.
+    fn end(&self, bank: usize) -> usize {
+        let mut offset = 0;
+        while offset + SLOT_SIZE <= self.storage.capacity() && !self.is_erased(bank, offset) {
+            offset += SLOT_SIZE;
+        }
+        offset
+    }
+
+    // Valid record with the highest sequence number, together with the bank holding it.
+    fn latest(&self) -> Option<(usize, u32, Settings)> {
+        (0..BANKS)
+            .flat_map(|bank| {
+                (0..self.end(bank))
+                    .step_by(SLOT_SIZE)
+                    .map(move |offset| (bank, offset))
+            })
+            .filter_map(|(bank, offset)| {
+                let mut slot = [0; SLOT_SIZE];
+                self.storage.read(bank, offset, &mut slot);
+                let (sequence, settings) = decode_record(&slot)?;
+                Some((bank, sequence, settings))
+            })
+            .max_by_key(|&(_, sequence, _)| sequence)
+    }
+
+    // Latest valid record, `None` if there is none and defaults should be used.
+    pub fn load(&self) -> Option<Settings> {
+        self.latest().map(|(_, _, settings)| settings)
+    }
+
+    // Erases the next bank, if there is no room for another record in the current one.
+    // Erase stalls the CPU for a while, so it's done ahead of `save`, without holding
+    // the resources needed to compose the record.
+    pub fn make_room(&mut self) -> Result<(), StorageError> {
+        if self.next + SLOT_SIZE > self.storage.capacity() {
+            let bank = (self.bank + 1) % BANKS;
+            self.storage.erase(bank)?;
+            self.bank = bank;
+            self.next = 0;
+        }
+        Ok(())
+    }
+
+    pub fn save(&mut self, settings: &Settings) -> Result<(), StorageError> {
+        self.make_room()?;
+
+        // flash wears out long before the sequence number could wrap
+        self.sequence += 1;
+        let mut slot = [0xff; SLOT_SIZE];
+        let len = encode_record(settings, self.sequence, &mut slot);
+
+        // slot is used up even if programming fails half way
+        let offset = self.next;
+        self.next += SLOT_SIZE;
+        self.storage.write(self.bank, offset, &slot[..len])
+    }
+}
@@ -0,0 +1,90 @@
+use crate::ir::{Frame, Timing};
+use crate::remote::RcButton;
+
+// Sony SIRC protocol, pulse-width coded, timing is given in [us]:
+// - start: 2.4 ms mark,
+// - each bit: 600 us space, followed by 1200 us (1) or 600 us (0) mark,
+// - bits sent LSB first: 7 command bits, then
+//   - 5 device bits (12-bit frame),
+//   - 8 device bits (15-bit frame),
+//   - 5 device bits and 8 extended bits (20-bit frame).
+// Held button repeats the whole frame every 45 ms, there is no repeat code.
+pub const START_MARK: Timing = Timing::new(2400, 400);
+const SPACE: Timing = Timing::new(600, 250);
+const ONE_MARK: Timing = Timing::new(1200, 300);
+const ZERO_MARK: Timing = Timing::new(600, 250);
+
+pub struct SircFrame {
+    pub device: u8,
+    pub extended: u8,
+    pub command: u8,
+}
+
+pub fn decode_frame(frame: &Frame) -> Option<SircFrame> {
+    let (&start, bits) = frame.durations().split_first()?;
+    if !START_MARK.matches(start) {
+        return None;
+    }
+
+    // a frame captured together with the following one ends with the longer space between them
+    let mut data: u32 = 0;
+    let mut width: u8 = 0;
+    for pair in bits.chunks(2) {
+        let [space, mark] = pair else {
+            break;
+        };
+        if !SPACE.matches(*space) || width == 20 {
+            break;
+        }
+        if ONE_MARK.matches(*mark) {
+            data |= 1 << width;
+        } else if !ZERO_MARK.matches(*mark) {
+            return None;
+        }
+        width += 1;
+    }
+
+    let command = (data & 0x7f) as u8;
+    let (device, extended) = match width {
+        12 => ((data >> 7) & 0x1f, 0),
+        15 => ((data >> 7) & 0xff, 0),
+        20 => ((data >> 7) & 0x1f, (data >> 12) & 0xff),
+        _ => return None,
+    };
+
+    Some(SircFrame {
+        device: device as u8,
+        extended: extended as u8,
+        command,
+    })
+}
+
+// Addresses consist of extended bits (upper byte) and device bits (lower byte).
+// Sony TV (12-bit frames, device 0x01). Digits around 5 move the pointer
+// in the same directions as the diagonal buttons of DV-MLG-20.
+const KEYMAP: [(u16, u8, RcButton); 17] = [
+    (0x0001, 0x74, RcButton::Up),
+    (0x0001, 0x75, RcButton::Down),
+    (0x0001, 0x34, RcButton::Left),
+    (0x0001, 0x33, RcButton::Right),
+    (0x0001, 0x65, RcButton::Ok),
+    (0x0001, 0x00, RcButton::Text),   // 1
+    (0x0001, 0x02, RcButton::MyApps), // 3
+    (0x0001, 0x06, RcButton::Back),   // 7
+    (0x0001, 0x08, RcButton::Exit),   // 9
+    (0x0001, 0x12, RcButton::VolumeUp),
+    (0x0001, 0x13, RcButton::VolumeDown),
+    (0x0001, 0x14, RcButton::Mute),
+    (0x0001, 0x10, RcButton::PageUp),   // channel +
+    (0x0001, 0x11, RcButton::PageDown), // channel -
+    (0x0001, 0x09, RcButton::Netflix),  // 0
+    (0x0001, 0x25, RcButton::Red),      // input
+    (0x0001, 0x60, RcButton::Green),    // home
+];
+
+pub fn lookup(address: u16, command: u8) -> Option<RcButton> {
+    KEYMAP
+        .iter()
+        .find(|(a, c, _)| *a == address && *c == command)
+        .map(|(_, _, button)| *button)
+}
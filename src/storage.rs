@@ -0,0 +1,18 @@
+#[derive(Clone, Copy, PartialEq, Eq, Debug, defmt::Format)]
+pub enum StorageError {
+    Erase,
+    Program,
+}
+
+// Number of banks, which are erased independently of each other.
+pub const BANKS: usize = 2;
+
+// Banks of NOR flash: erasing sets all the bytes of a bank to 0xff,
+// programming can only clear bits. Offsets are relative to the start of the bank.
+pub trait Storage {
+    // size of each bank
+    fn capacity(&self) -> usize;
+    fn read(&self, bank: usize, offset: usize, bytes: &mut [u8]);
+    fn write(&mut self, bank: usize, offset: usize, bytes: &[u8]) -> Result<(), StorageError>;
+    fn erase(&mut self, bank: usize) -> Result<(), StorageError>;
+}